@@ -0,0 +1,164 @@
+//! Parsing of credits balances as reported by the usage API
+
+/// A parsed credits balance
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreditsBalance {
+    pub amount: f64,
+    /// ISO 4217 code when the text carried a symbol or code (e.g. "USD")
+    pub currency: Option<String>,
+}
+
+/// Parse balances such as "12.50", "$1,234.50", "-$5.00", "($5.00)",
+/// "1.234,50 €" or "EUR 12". Returns `None` for anything that is not a number.
+pub fn parse_credits_balance(text: &str) -> Option<CreditsBalance> {
+    let mut rest = text.trim();
+    let mut negative = false;
+    let mut currency = None;
+
+    // Accounting notation for negative amounts
+    if let Some(inner) = rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) {
+        negative = true;
+        rest = inner.trim();
+    }
+    if let Some(inner) = rest.strip_prefix('-') {
+        negative = !negative;
+        rest = inner.trim();
+    }
+
+    // Currency symbol or code on either side
+    if let Some((code, inner)) = strip_currency_prefix(rest) {
+        currency = Some(code);
+        rest = inner.trim();
+    }
+    if let Some((code, inner)) = strip_currency_suffix(rest) {
+        currency = currency.or(Some(code));
+        rest = inner.trim();
+    }
+    if let Some(inner) = rest.strip_prefix('-') {
+        negative = !negative;
+        rest = inner.trim();
+    }
+
+    let amount = parse_number(rest)?;
+    Some(CreditsBalance {
+        amount: if negative { -amount } else { amount },
+        currency,
+    })
+}
+
+/// Format an amount with its currency, e.g. "$5.00" or "5.00 EUR"
+pub fn format_credits_amount(amount: f64, currency: Option<&str>) -> String {
+    match currency {
+        Some("USD") | None if amount < 0.0 => format!("-${:.2}", -amount),
+        Some("USD") | None => format!("${:.2}", amount),
+        Some(code) => format!("{:.2} {}", amount, code),
+    }
+}
+
+const CURRENCY_SYMBOLS: [(&str, &str); 4] =
+    [("$", "USD"), ("€", "EUR"), ("£", "GBP"), ("¥", "JPY")];
+
+fn strip_currency_prefix(text: &str) -> Option<(String, &str)> {
+    for (symbol, code) in CURRENCY_SYMBOLS {
+        if let Some(rest) = text.strip_prefix(symbol) {
+            return Some((code.to_string(), rest));
+        }
+    }
+    let code = text.get(..3)?;
+    is_currency_code(code).then(|| (code.to_string(), &text[3..]))
+}
+
+fn strip_currency_suffix(text: &str) -> Option<(String, &str)> {
+    for (symbol, code) in CURRENCY_SYMBOLS {
+        if let Some(rest) = text.strip_suffix(symbol) {
+            return Some((code.to_string(), rest));
+        }
+    }
+    let split = text.len().checked_sub(3)?;
+    let code = text.get(split..)?;
+    is_currency_code(code).then(|| (code.to_string(), &text[..split]))
+}
+
+fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// Parse a number with optional thousands separators, accepting either
+/// "." or "," as the decimal separator
+fn parse_number(text: &str) -> Option<f64> {
+    let digits: String = text
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '\'' | '\u{a0}'))
+        .collect();
+    if digits.is_empty()
+        || !digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    {
+        return None;
+    }
+
+    // The right-most separator is the decimal one, unless a lone separator
+    // groups exactly three digits (e.g. "1,234")
+    let normalized = match (digits.rfind('.'), digits.rfind(',')) {
+        (Some(dot), Some(comma)) if dot > comma => digits.replace(',', ""),
+        (Some(_), Some(_)) => digits.replace('.', "").replace(',', "."),
+        (None, Some(_)) if is_thousands_grouping(&digits, ',') => digits.replace(',', ""),
+        (None, Some(_)) => digits.replace(',', "."),
+        (Some(_), None) if digits.matches('.').count() > 1 => digits.replace('.', ""),
+        _ => digits,
+    };
+
+    normalized.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn is_thousands_grouping(digits: &str, separator: char) -> bool {
+    let mut groups = digits.split(separator);
+    let first = groups.next().unwrap_or_default();
+    !first.is_empty() && first.len() <= 3 && groups.all(|g| g.len() == 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Option<(f64, Option<String>)> {
+        parse_credits_balance(text).map(|b| (b.amount, b.currency))
+    }
+
+    fn amount(amount: f64, currency: Option<&str>) -> Option<(f64, Option<String>)> {
+        Some((amount, currency.map(String::from)))
+    }
+
+    #[test]
+    fn parses_thousands_separators() {
+        assert_eq!(parse("1,234.50"), amount(1234.5, None));
+        assert_eq!(parse("$1,234.50"), amount(1234.5, Some("USD")));
+        assert_eq!(parse("1,234"), amount(1234.0, None));
+        assert_eq!(parse("1.234,50 €"), amount(1234.5, Some("EUR")));
+        assert_eq!(parse("12,5"), amount(12.5, None));
+    }
+
+    #[test]
+    fn parses_negative_amounts() {
+        assert_eq!(parse("-5"), amount(-5.0, None));
+        assert_eq!(parse("-$5.00"), amount(-5.0, Some("USD")));
+        assert_eq!(parse("$-5.00"), amount(-5.0, Some("USD")));
+        assert_eq!(parse("-1,234.50 EUR"), amount(-1234.5, Some("EUR")));
+    }
+
+    #[test]
+    fn parses_accounting_notation() {
+        assert_eq!(parse("($5.00)"), amount(-5.0, Some("USD")));
+        assert_eq!(parse("(1,234.50)"), amount(-1234.5, None));
+        assert_eq!(parse("( EUR 12 )"), amount(-12.0, Some("EUR")));
+    }
+
+    #[test]
+    fn rejects_non_numbers() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("unlimited"), None);
+        assert_eq!(parse("$"), None);
+        assert_eq!(parse("12 credits"), None);
+    }
+}
@@ -0,0 +1,45 @@
+//! Shared HTTP client built from the app settings
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::settings::load_settings;
+use crate::types::HttpSettings;
+
+/// Client plus the settings it was built from, so it is rebuilt when they change
+static CLIENT: Mutex<Option<(HttpSettings, reqwest::Client)>> = Mutex::new(None);
+
+/// Get the shared HTTP client.
+/// Connections are pooled across calls; the client is rebuilt after the
+/// HTTP settings change.
+pub fn http_client() -> Result<reqwest::Client> {
+    let settings = load_settings()?.http;
+
+    let mut cached = CLIENT.lock().unwrap();
+    if let Some((built_from, client)) = cached.as_ref() {
+        if *built_from == settings {
+            return Ok(client.clone());
+        }
+    }
+
+    let client = build_client(&settings)?;
+    *cached = Some((settings, client.clone()));
+    Ok(client)
+}
+
+fn build_client(settings: &HttpSettings) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(settings.request_timeout_secs))
+        .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+        .pool_idle_timeout(Duration::from_secs(settings.pool_idle_timeout_secs));
+
+    if let Some(proxy) = settings.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+        let proxy = reqwest::Proxy::all(proxy.trim())
+            .with_context(|| format!("Invalid proxy URL: {proxy}"))?;
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().context("Failed to build HTTP client")
+}
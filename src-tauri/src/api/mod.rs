@@ -1,5 +1,16 @@
 //! API client module
 
+pub mod credits;
+pub mod http;
+pub mod openai_usage;
+pub mod rate_limit_headers;
 pub mod usage;
+pub mod usage_cache;
+pub mod usage_summary;
+pub mod workspaces;
 
+pub use http::*;
 pub use usage::*;
+pub use usage_cache::*;
+pub use usage_summary::*;
+pub use workspaces::*;
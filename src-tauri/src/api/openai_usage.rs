@@ -0,0 +1,330 @@
+//! Usage for API key accounts, from the OpenAI organization usage and costs APIs
+//!
+//! The organization endpoints need an admin key. Regular project keys only get
+//! the rate limits reported in the headers of a probe request, which admin keys
+//! in turn may be refused.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use super::http_client;
+use super::rate_limit_headers::parse_rate_limit_headers;
+use super::usage::{classify_usage_error, retry_after_secs, usage_error_from_status};
+use crate::settings::endpoint_config;
+use crate::types::{ApiKeyUsage, RateLimitSnapshot, UsageError, UsageInfo};
+
+const CODEX_USER_AGENT: &str = "codex-cli/1.0.0";
+
+/// Daily buckets cover a month in one page; stop following pages after this many
+const MAX_PAGES: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    // A plain `default` would require `T: Default`
+    #[serde(default = "Vec::new")]
+    data: Vec<Bucket<T>>,
+    #[serde(default)]
+    has_more: bool,
+    #[serde(default)]
+    next_page: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Bucket<T> {
+    #[serde(default = "Vec::new")]
+    results: Vec<T>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CompletionsResult {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    input_cached_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    num_model_requests: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CostsResult {
+    #[serde(default)]
+    amount: Option<CostAmount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostAmount {
+    #[serde(default)]
+    value: f64,
+    #[serde(default)]
+    currency: Option<String>,
+}
+
+/// Get usage for an API key account
+pub async fn get_api_key_usage(account_id: &str, api_key: &str) -> Result<UsageInfo> {
+    let base = endpoint_config()?.openai_api;
+    let client = http_client()?;
+    Ok(api_key_usage(&client, &base, account_id, api_key).await)
+}
+
+async fn api_key_usage(
+    client: &reqwest::Client,
+    base: &str,
+    account_id: &str,
+    api_key: &str,
+) -> UsageInfo {
+    // Project keys only pass the probe and admin keys only the organization
+    // endpoints, so neither failing on its own is an error
+    let probe = probe_rate_limits(client, base, api_key).await;
+
+    let period_start = month_start(Utc::now());
+    let mut api_usage = ApiKeyUsage {
+        period_start: Some(period_start),
+        ..ApiKeyUsage::default()
+    };
+
+    match fetch_pages::<CompletionsResult>(client, base, "usage/completions", api_key, period_start)
+        .await
+    {
+        Ok(Some(results)) => {
+            api_usage.organization_access = true;
+            api_usage.input_tokens = Some(results.iter().map(|r| r.input_tokens).sum());
+            api_usage.cached_input_tokens =
+                Some(results.iter().map(|r| r.input_cached_tokens).sum());
+            api_usage.output_tokens = Some(results.iter().map(|r| r.output_tokens).sum());
+            api_usage.requests = Some(results.iter().map(|r| r.num_model_requests).sum());
+        }
+        Ok(None) => println!("[Usage] API key has no access to organization usage"),
+        Err(e) => println!("[Usage] Failed to fetch organization usage: {e:#}"),
+    }
+
+    match fetch_pages::<CostsResult>(client, base, "costs", api_key, period_start).await {
+        Ok(Some(results)) => {
+            api_usage.organization_access = true;
+            let amounts: Vec<&CostAmount> =
+                results.iter().filter_map(|r| r.amount.as_ref()).collect();
+            api_usage.cost = Some(amounts.iter().map(|a| a.value).sum());
+            api_usage.currency = amounts.iter().find_map(|a| a.currency.clone());
+        }
+        Ok(None) => println!("[Usage] API key has no access to organization costs"),
+        Err(e) => println!("[Usage] Failed to fetch organization costs: {e:#}"),
+    }
+
+    let rate_limits = match probe {
+        Ok(rate_limits) => rate_limits,
+        Err(error) if !api_usage.organization_access => {
+            return UsageInfo::failed(account_id.to_string(), error);
+        }
+        Err(error) => {
+            println!("[Usage] API key probe failed: {}", error.message);
+            None
+        }
+    };
+
+    // Request and token limits stay in the snapshot: they are per-minute API
+    // limits, not the Codex windows the primary/secondary fields describe
+    UsageInfo {
+        plan_type: Some("api_key".to_string()),
+        api_key_usage: Some(api_usage),
+        rate_limit_snapshot: rate_limits,
+        ..UsageInfo::new(account_id.to_string())
+    }
+}
+
+/// Send a `/models` request and read the rate limits from its headers
+async fn probe_rate_limits(
+    client: &reqwest::Client,
+    base: &str,
+    api_key: &str,
+) -> Result<Option<RateLimitSnapshot>, UsageError> {
+    let response = client
+        .get(format!("{base}/models"))
+        .header(USER_AGENT, CODEX_USER_AGENT)
+        .header(AUTHORIZATION, format!("Bearer {api_key}"))
+        .send()
+        .await
+        .context("Failed to send API key probe request")
+        .map_err(|e| classify_usage_error(&e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = retry_after_secs(response.headers());
+        let body = response.text().await.unwrap_or_default();
+        return Err(usage_error_from_status(status, retry_after, &body));
+    }
+
+    Ok(parse_rate_limit_headers(response.headers()))
+}
+
+/// Fetch every daily bucket since `start` from an organization endpoint.
+/// Returns `None` when the key is not allowed to read it.
+async fn fetch_pages<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    base: &str,
+    path: &str,
+    api_key: &str,
+    start: DateTime<Utc>,
+) -> Result<Option<Vec<T>>> {
+    let mut results = Vec::new();
+    let mut page_cursor: Option<String> = None;
+
+    for _ in 0..MAX_PAGES {
+        let mut query = vec![
+            ("start_time", start.timestamp().to_string()),
+            ("bucket_width", "1d".to_string()),
+            ("limit", "31".to_string()),
+        ];
+        if let Some(cursor) = &page_cursor {
+            query.push(("page", cursor.clone()));
+        }
+
+        let response = client
+            .get(format!("{base}/organization/{path}"))
+            .header(USER_AGENT, CODEX_USER_AGENT)
+            .header(AUTHORIZATION, format!("Bearer {api_key}"))
+            .query(&query)
+            .send()
+            .await
+            .with_context(|| format!("Failed to request organization {path}"))?;
+
+        let status = response.status();
+        if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let retry_after = retry_after_secs(response.headers());
+            let body = response.text().await.unwrap_or_default();
+            let error = usage_error_from_status(status, retry_after, &body);
+            anyhow::bail!("Organization {path} request failed: {}", error.message);
+        }
+
+        let page: Page<T> = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse organization {path} response"))?;
+        results.extend(page.data.into_iter().flat_map(|bucket| bucket.results));
+
+        match page.next_page {
+            Some(next) if page.has_more => page_cursor = Some(next),
+            _ => break,
+        }
+    }
+
+    Ok(Some(results))
+}
+
+fn month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{StandIn, StandInResponse};
+    use crate::types::UsageErrorKind;
+
+    const COMPLETIONS_PAGE: &str = r#"{
+        "object": "page",
+        "data": [
+            { "results": [{ "input_tokens": 1000, "input_cached_tokens": 200, "output_tokens": 300, "num_model_requests": 4 }] },
+            { "results": [{ "input_tokens": 500, "output_tokens": 100, "num_model_requests": 1 }] }
+        ],
+        "has_more": false,
+        "next_page": null
+    }"#;
+
+    const COSTS_PAGE: &str = r#"{
+        "object": "page",
+        "data": [
+            { "results": [{ "amount": { "value": 1.25, "currency": "usd" } }] },
+            { "results": [{ "amount": { "value": 0.5, "currency": "usd" } }] }
+        ],
+        "has_more": false
+    }"#;
+
+    async fn usage_from(responses: Vec<StandInResponse>) -> (UsageInfo, StandIn) {
+        let stand_in = StandIn::start(responses).await;
+        let usage = api_key_usage(&reqwest::Client::new(), &stand_in.url, "acc", "sk-test").await;
+        (usage, stand_in)
+    }
+
+    #[tokio::test]
+    async fn admin_key_reads_organization_usage_when_probe_is_refused() {
+        let (usage, stand_in) = usage_from(vec![
+            StandInResponse::new(403, r#"{"error":{"message":"Missing scopes"}}"#),
+            StandInResponse::new(200, COMPLETIONS_PAGE),
+            StandInResponse::new(200, COSTS_PAGE),
+        ])
+        .await;
+
+        assert!(usage.error.is_none());
+        let api = usage.api_key_usage.unwrap();
+        assert!(api.organization_access);
+        assert_eq!(api.input_tokens, Some(1500));
+        assert_eq!(api.cached_input_tokens, Some(200));
+        assert_eq!(api.output_tokens, Some(400));
+        assert_eq!(api.requests, Some(5));
+        assert_eq!(api.cost, Some(1.75));
+        assert_eq!(api.currency.as_deref(), Some("usd"));
+        assert!(usage.rate_limit_snapshot.is_none());
+
+        let paths: Vec<String> = stand_in.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths[0], "/models");
+        assert!(paths[1].starts_with("/organization/usage/completions?"));
+        assert!(paths[2].starts_with("/organization/costs?"));
+    }
+
+    #[tokio::test]
+    async fn project_key_keeps_header_limits_out_of_usage_windows() {
+        let (usage, _stand_in) = usage_from(vec![
+            StandInResponse::new(200, r#"{"data":[]}"#)
+                .header("x-ratelimit-limit-requests", "500")
+                .header("x-ratelimit-remaining-requests", "499")
+                .header("x-ratelimit-limit-tokens", "30000")
+                .header("x-ratelimit-remaining-tokens", "15000"),
+            StandInResponse::new(403, r#"{"error":{"message":"Admin key required"}}"#),
+        ])
+        .await;
+
+        assert!(usage.error.is_none());
+        assert!(!usage.api_key_usage.unwrap().organization_access);
+        assert_eq!(usage.primary_used_percent, None);
+        assert_eq!(usage.secondary_used_percent, None);
+        let snapshot = usage.rate_limit_snapshot.unwrap();
+        assert_eq!(snapshot.requests.unwrap().remaining, Some(499));
+        assert_eq!(snapshot.tokens.unwrap().limit, Some(30_000));
+    }
+
+    #[tokio::test]
+    async fn rejected_key_reports_probe_error_with_body() {
+        let (usage, _stand_in) = usage_from(vec![StandInResponse::new(
+            401,
+            r#"{"error":{"message":"Incorrect API key provided"}}"#,
+        )])
+        .await;
+
+        let error = usage.error_detail.unwrap();
+        assert_eq!(error.kind, UsageErrorKind::Unauthorized);
+        assert_eq!(error.status, Some(401));
+        assert!(error.message.contains("Incorrect API key provided"));
+    }
+
+    #[tokio::test]
+    async fn rate_limited_probe_keeps_retry_after() {
+        let (usage, _stand_in) = usage_from(vec![
+            StandInResponse::new(429, "").header("retry-after", "20"),
+            StandInResponse::new(403, ""),
+        ])
+        .await;
+
+        let error = usage.error_detail.unwrap();
+        assert_eq!(error.kind, UsageErrorKind::RateLimited);
+        assert_eq!(error.retry_after_secs, Some(20));
+    }
+}
@@ -0,0 +1,279 @@
+//! Rate limits reported in response headers
+//!
+//! The Codex backend sends `x-codex-*` headers and the OpenAI API sends
+//! `x-ratelimit-*` headers on regular responses. They are a second source of
+//! truth next to the `/wham/usage` payload.
+
+use chrono::{Duration, Utc};
+use reqwest::header::HeaderMap;
+
+use crate::types::{HeaderLimit, RateLimitSnapshot, SnapshotWindow, UsageInfo};
+
+/// Parse rate limit headers. Returns `None` when the response carries none.
+pub fn parse_rate_limit_headers(headers: &HeaderMap) -> Option<RateLimitSnapshot> {
+    let snapshot = RateLimitSnapshot {
+        captured_at: Utc::now(),
+        primary: codex_window(headers, "primary"),
+        secondary: codex_window(headers, "secondary"),
+        has_credits: header_bool(headers, "x-codex-credits-has-credits"),
+        unlimited_credits: header_bool(headers, "x-codex-credits-unlimited"),
+        credits_balance: header_str(headers, "x-codex-credits-balance").map(String::from),
+        requests: openai_limit(headers, "requests"),
+        tokens: openai_limit(headers, "tokens"),
+    };
+
+    let empty = snapshot.primary.is_none()
+        && snapshot.secondary.is_none()
+        && snapshot.has_credits.is_none()
+        && snapshot.unlimited_credits.is_none()
+        && snapshot.credits_balance.is_none()
+        && snapshot.requests.is_none()
+        && snapshot.tokens.is_none();
+
+    (!empty).then_some(snapshot)
+}
+
+/// Fill windows and credits missing from the usage payload with the header snapshot
+pub fn fill_missing_from_snapshot(usage: &mut UsageInfo, snapshot: &RateLimitSnapshot) {
+    if usage.primary_used_percent.is_none() {
+        if let Some(window) = &snapshot.primary {
+            usage.primary_used_percent = Some(window.used_percent);
+            usage.primary_window_minutes = window.window_minutes;
+            usage.primary_resets_at = window.resets_at;
+        }
+    }
+
+    if usage.secondary_used_percent.is_none() {
+        if let Some(window) = &snapshot.secondary {
+            usage.secondary_used_percent = Some(window.used_percent);
+            usage.secondary_window_minutes = window.window_minutes;
+            usage.secondary_resets_at = window.resets_at;
+        }
+    }
+
+    if usage.has_credits.is_none() {
+        usage.has_credits = snapshot.has_credits;
+    }
+    if usage.unlimited_credits.is_none() {
+        usage.unlimited_credits = snapshot.unlimited_credits;
+    }
+    if usage.credits_balance.is_none() {
+        usage.credits_balance = snapshot.credits_balance.clone();
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+fn header_f64(headers: &HeaderMap, name: &str) -> Option<f64> {
+    header_str(headers, name).and_then(|v| v.parse().ok())
+}
+
+fn header_i64(headers: &HeaderMap, name: &str) -> Option<i64> {
+    header_str(headers, name).and_then(|v| v.parse().ok())
+}
+
+fn header_bool(headers: &HeaderMap, name: &str) -> Option<bool> {
+    match header_str(headers, name)?.to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// `x-codex-{primary,secondary}-*` window headers
+fn codex_window(headers: &HeaderMap, name: &str) -> Option<SnapshotWindow> {
+    let used_percent = header_f64(headers, &format!("x-codex-{name}-used-percent"))?;
+    let window_minutes = header_i64(headers, &format!("x-codex-{name}-window-minutes"));
+
+    // Newer backends send an absolute reset time, older ones a delay
+    let resets_at = header_i64(headers, &format!("x-codex-{name}-reset-at")).or_else(|| {
+        header_i64(headers, &format!("x-codex-{name}-reset-after-seconds"))
+            .map(|delay| Utc::now().timestamp() + delay)
+    });
+
+    Some(SnapshotWindow {
+        used_percent,
+        window_minutes,
+        resets_at,
+    })
+}
+
+/// `x-ratelimit-{limit,remaining,reset}-{requests,tokens}` headers
+fn openai_limit(headers: &HeaderMap, name: &str) -> Option<HeaderLimit> {
+    let limit = header_f64(headers, &format!("x-ratelimit-limit-{name}"));
+    let remaining = header_f64(headers, &format!("x-ratelimit-remaining-{name}"));
+    let resets_at = header_str(headers, &format!("x-ratelimit-reset-{name}"))
+        .and_then(parse_reset_duration)
+        .map(|delay| (Utc::now() + delay).timestamp());
+
+    if limit.is_none() && remaining.is_none() && resets_at.is_none() {
+        return None;
+    }
+
+    Some(HeaderLimit {
+        limit: limit.map(|v| v as u64),
+        remaining: remaining.map(|v| v as u64),
+        resets_at,
+    })
+}
+
+/// Parse reset delays such as "1s", "6m0s", "20ms" or "1h2m3.5s"
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut total_ms = 0.0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit_len] {
+            "ms" => 1.0,
+            "s" | "" => 1_000.0,
+            "m" => 60_000.0,
+            "h" => 3_600_000.0,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total_ms += number * factor;
+    }
+
+    Some(Duration::milliseconds(total_ms.round() as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    HeaderName::from_static(name),
+                    HeaderValue::from_str(value).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_reset_durations() {
+        let ms = |value| parse_reset_duration(value).map(|d| d.num_milliseconds());
+
+        assert_eq!(ms("1m30s"), Some(90_000));
+        assert_eq!(ms("250ms"), Some(250));
+        assert_eq!(ms("20"), Some(20_000));
+        assert_eq!(ms("1.5"), Some(1_500));
+        assert_eq!(ms("6m0s"), Some(360_000));
+        assert_eq!(ms("1h2m3.5s"), Some(3_723_500));
+        assert_eq!(ms(" 2s "), Some(2_000));
+    }
+
+    #[test]
+    fn rejects_malformed_reset_durations() {
+        for value in ["", "soon", "5x", "m5", "-1s", "1..5s"] {
+            assert_eq!(parse_reset_duration(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn parses_codex_headers() {
+        let now = Utc::now().timestamp();
+        let snapshot = parse_rate_limit_headers(&headers(&[
+            ("x-codex-primary-used-percent", "42.5"),
+            ("x-codex-primary-window-minutes", "300"),
+            ("x-codex-primary-reset-at", "1900000000"),
+            ("x-codex-secondary-used-percent", "80"),
+            ("x-codex-secondary-reset-after-seconds", "3600"),
+            ("x-codex-credits-has-credits", "True"),
+            ("x-codex-credits-unlimited", "0"),
+            ("x-codex-credits-balance", " 12.50 "),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            snapshot.primary,
+            Some(SnapshotWindow {
+                used_percent: 42.5,
+                window_minutes: Some(300),
+                resets_at: Some(1_900_000_000),
+            })
+        );
+        let secondary = snapshot.secondary.unwrap();
+        assert_eq!(secondary.used_percent, 80.0);
+        assert_eq!(secondary.window_minutes, None);
+        assert!((secondary.resets_at.unwrap() - (now + 3600)).abs() <= 5);
+        assert_eq!(snapshot.has_credits, Some(true));
+        assert_eq!(snapshot.unlimited_credits, Some(false));
+        assert_eq!(snapshot.credits_balance.as_deref(), Some("12.50"));
+        assert_eq!(snapshot.requests, None);
+    }
+
+    #[test]
+    fn parses_openai_headers() {
+        let now = Utc::now().timestamp();
+        let snapshot = parse_rate_limit_headers(&headers(&[
+            ("x-ratelimit-limit-requests", "500"),
+            ("x-ratelimit-remaining-requests", "499"),
+            ("x-ratelimit-reset-requests", "120ms"),
+            ("x-ratelimit-limit-tokens", "30000"),
+            ("x-ratelimit-reset-tokens", "1m30s"),
+        ]))
+        .unwrap();
+
+        let requests = snapshot.requests.unwrap();
+        assert_eq!(requests.limit, Some(500));
+        assert_eq!(requests.remaining, Some(499));
+        let tokens = snapshot.tokens.unwrap();
+        assert_eq!(tokens.limit, Some(30_000));
+        assert_eq!(tokens.remaining, None);
+        assert!((tokens.resets_at.unwrap() - (now + 90)).abs() <= 5);
+        assert!(snapshot.primary.is_none());
+    }
+
+    #[test]
+    fn ignores_missing_and_malformed_headers() {
+        assert!(parse_rate_limit_headers(&HeaderMap::new()).is_none());
+        assert!(parse_rate_limit_headers(&headers(&[
+            ("x-codex-primary-used-percent", "lots"),
+            ("x-codex-credits-has-credits", "maybe"),
+            ("x-codex-credits-balance", "  "),
+            ("content-type", "application/json"),
+        ]))
+        .is_none());
+    }
+
+    #[test]
+    fn snapshot_only_fills_missing_usage() {
+        let snapshot = parse_rate_limit_headers(&headers(&[
+            ("x-codex-primary-used-percent", "10"),
+            ("x-codex-secondary-used-percent", "20"),
+            ("x-codex-credits-balance", "5"),
+        ]))
+        .unwrap();
+        let mut usage = UsageInfo::new("acc".to_string());
+        usage.primary_used_percent = Some(55.0);
+
+        fill_missing_from_snapshot(&mut usage, &snapshot);
+
+        assert_eq!(usage.primary_used_percent, Some(55.0));
+        assert_eq!(usage.secondary_used_percent, Some(20.0));
+        assert_eq!(usage.credits_balance.as_deref(), Some("5"));
+    }
+}
@@ -1,44 +1,91 @@
 //! Usage API client for fetching rate limits and credits
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt};
 use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, USER_AGENT},
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, RETRY_AFTER, USER_AGENT},
     StatusCode,
 };
 
-use crate::auth::{ensure_chatgpt_tokens_fresh, refresh_chatgpt_tokens};
+use super::http_client;
+use super::openai_usage::get_api_key_usage;
+use super::rate_limit_headers::{fill_missing_from_snapshot, parse_rate_limit_headers};
+use super::usage_cache::{
+    fresh_cached_usage, last_cached_usage, record_rate_limit_snapshot, store_cached_usage,
+};
+use crate::auth::{ensure_chatgpt_tokens_fresh, refresh_chatgpt_tokens, TokenRefreshRejected};
+use crate::settings::{endpoint_config, load_settings};
 use crate::types::{
-    AuthData, CreditStatusDetails, RateLimitDetails, RateLimitStatusPayload, RateLimitWindow,
-    StoredAccount, UsageInfo,
+    AuthData, RateLimitSnapshot, RateLimitStatusPayload, StoredAccount, UsageError, UsageErrorKind,
+    UsageFreshness, UsageInfo, UsageSettings, UsageWindow,
 };
 
-const CHATGPT_BACKEND_API: &str = "https://chatgpt.com/backend-api";
-const OPENAI_API: &str = "https://api.openai.com/v1";
 const CODEX_USER_AGENT: &str = "codex-cli/1.0.0";
 
 /// Get usage information for an account
 pub async fn get_account_usage(account: &StoredAccount) -> Result<UsageInfo> {
     println!("[Usage] Fetching usage for account: {}", account.name);
 
-    match &account.auth_data {
-        AuthData::ApiKey { .. } => {
-            println!("[Usage] API key accounts don't support usage info");
-            Ok(UsageInfo {
-                account_id: account.id.clone(),
-                plan_type: Some("api_key".to_string()),
-                primary_used_percent: None,
-                primary_window_minutes: None,
-                primary_resets_at: None,
-                secondary_used_percent: None,
-                secondary_window_minutes: None,
-                secondary_resets_at: None,
-                has_credits: None,
-                unlimited_credits: None,
-                credits_balance: None,
-                error: Some("Usage info not available for API key accounts".to_string()),
-            })
-        }
+    if account.is_signed_out() {
+        return Ok(UsageInfo::failed(
+            account.id.clone(),
+            UsageError::new(
+                UsageErrorKind::Unsupported,
+                "Signed out; log in again to see usage",
+            ),
+        ));
+    }
+
+    let result = match &account.auth_data {
+        AuthData::ApiKey { key } => get_api_key_usage(&account.id, key).await,
         AuthData::ChatGPT { .. } => get_usage_with_chatgpt_auth(account).await,
+    };
+
+    match result {
+        Ok(usage) => Ok(usage),
+        Err(e) => {
+            println!("[Usage] Error for {}: {e:#}", account.name);
+            Ok(UsageInfo::failed(
+                account.id.clone(),
+                classify_usage_error(&e),
+            ))
+        }
+    }
+}
+
+/// Get usage for an account, served from the cache while it is younger than the TTL.
+/// When a fetch fails, the last good usage is returned marked as stale, with the
+/// new error attached.
+pub async fn get_account_usage_cached(
+    account: &StoredAccount,
+    force_refresh: bool,
+) -> Result<UsageInfo> {
+    if !force_refresh {
+        if let Some(mut cached) = fresh_cached_usage(&account.id) {
+            cached.freshness = UsageFreshness::Cached;
+            return Ok(cached);
+        }
+    }
+
+    let usage = get_account_usage(account).await?;
+    let Some(error) = usage.error.as_deref() else {
+        store_cached_usage(&usage);
+        return Ok(usage);
+    };
+
+    match last_cached_usage(&account.id) {
+        Some(mut stale) => {
+            println!(
+                "[Usage] Fetch failed for {}, keeping last good usage: {error}",
+                account.name
+            );
+            stale.freshness = UsageFreshness::StaleAfterError;
+            stale.error = usage.error;
+            stale.error_detail = usage.error_detail;
+            Ok(stale)
+        }
+        None => Ok(usage),
     }
 }
 
@@ -50,7 +97,7 @@ pub async fn warmup_account(account: &StoredAccount) -> Result<()> {
     );
 
     match &account.auth_data {
-        AuthData::ApiKey { key } => warmup_with_api_key(key).await,
+        AuthData::ApiKey { key } => warmup_with_api_key(&account.id, key).await,
         AuthData::ChatGPT { .. } => warmup_with_chatgpt_auth(account).await,
     }
 }
@@ -59,7 +106,8 @@ async fn get_usage_with_chatgpt_auth(account: &StoredAccount) -> Result<UsageInf
     let fresh_account = ensure_chatgpt_tokens_fresh(account).await?;
     let (access_token, chatgpt_account_id) = extract_chatgpt_auth(&fresh_account)?;
 
-    let response = send_chatgpt_usage_request(access_token, chatgpt_account_id).await?;
+    let (response, rate_limits) =
+        send_chatgpt_usage_request(access_token, chatgpt_account_id).await?;
     if response.status() == StatusCode::UNAUTHORIZED {
         println!(
             "[Usage] Unauthorized for account {}, refreshing token and retrying once",
@@ -67,33 +115,46 @@ async fn get_usage_with_chatgpt_auth(account: &StoredAccount) -> Result<UsageInf
         );
         let refreshed_account = refresh_chatgpt_tokens(&fresh_account).await?;
         let (retry_token, retry_account_id) = extract_chatgpt_auth(&refreshed_account)?;
-        let retry_response = send_chatgpt_usage_request(retry_token, retry_account_id).await?;
+        let (retry_response, retry_rate_limits) =
+            send_chatgpt_usage_request(retry_token, retry_account_id).await?;
         return parse_usage_response(
             &refreshed_account.id,
             &refreshed_account.name,
             retry_response,
+            retry_rate_limits,
         )
         .await;
     }
 
-    parse_usage_response(&fresh_account.id, &fresh_account.name, response).await
+    parse_usage_response(
+        &fresh_account.id,
+        &fresh_account.name,
+        response,
+        rate_limits,
+    )
+    .await
 }
 
 async fn parse_usage_response(
     account_id: &str,
     account_name: &str,
     response: reqwest::Response,
+    rate_limits: Option<RateLimitSnapshot>,
 ) -> Result<UsageInfo> {
     let status = response.status();
     println!("[Usage] Response status: {status}");
 
     if !status.is_success() {
+        let retry_after = retry_after_secs(response.headers());
         let body = response.text().await.unwrap_or_default();
         println!("[Usage] Error response: {body}");
-        return Ok(UsageInfo::error(
-            account_id.to_string(),
-            format!("API error: {status}"),
-        ));
+        return Ok(UsageInfo {
+            rate_limit_snapshot: rate_limits,
+            ..UsageInfo::failed(
+                account_id.to_string(),
+                usage_error_from_status(status, retry_after, &body),
+            )
+        });
     }
 
     let body_text = response
@@ -102,15 +163,10 @@ async fn parse_usage_response(
         .context("Failed to read response body")?;
     println!(
         "[Usage] Response body: {}",
-        &body_text[..body_text.len().min(200)]
+        body_text.chars().take(200).collect::<String>()
     );
 
-    let payload: RateLimitStatusPayload =
-        serde_json::from_str(&body_text).context("Failed to parse usage response")?;
-
-    println!("[Usage] Parsed plan_type: {}", payload.plan_type);
-
-    let usage = convert_payload_to_usage_info(account_id, payload);
+    let usage = usage_from_body(account_id, &body_text, rate_limits);
     println!(
         "[Usage] {} - primary: {:?}%, plan: {:?}",
         account_name, usage.primary_used_percent, usage.plan_type
@@ -119,11 +175,135 @@ async fn parse_usage_response(
     Ok(usage)
 }
 
+/// Build usage from a successful response body. The body is kept as `raw_payload`
+/// and header limits fill missing windows even when the body cannot be parsed.
+fn usage_from_body(
+    account_id: &str,
+    body_text: &str,
+    rate_limits: Option<RateLimitSnapshot>,
+) -> UsageInfo {
+    let raw = serde_json::from_str::<serde_json::Value>(body_text);
+    let parsed = raw.as_ref().map_err(|e| e.to_string()).and_then(|raw| {
+        serde_json::from_value::<RateLimitStatusPayload>(raw.clone()).map_err(|e| e.to_string())
+    });
+
+    let mut usage = match parsed {
+        Ok(payload) => {
+            println!("[Usage] Parsed plan_type: {:?}", payload.plan_type);
+            convert_payload_to_usage_info(account_id, payload)
+        }
+        Err(e) => UsageInfo::failed(
+            account_id.to_string(),
+            UsageError::new(
+                UsageErrorKind::Parse,
+                format!("Failed to parse usage response: {e}"),
+            ),
+        ),
+    };
+    usage.raw_payload =
+        Some(raw.unwrap_or_else(|_| serde_json::Value::String(body_text.to_string())));
+    if let Some(snapshot) = rate_limits {
+        fill_missing_from_snapshot(&mut usage, &snapshot);
+        usage.rate_limit_snapshot = Some(snapshot);
+    }
+    usage
+}
+
+/// Classify an error response from the usage API
+pub(super) fn usage_error_from_status(
+    status: StatusCode,
+    retry_after_secs: Option<u64>,
+    body: &str,
+) -> UsageError {
+    let kind = match status {
+        StatusCode::UNAUTHORIZED => UsageErrorKind::Unauthorized,
+        StatusCode::FORBIDDEN | StatusCode::PAYMENT_REQUIRED => UsageErrorKind::Forbidden,
+        StatusCode::TOO_MANY_REQUESTS => UsageErrorKind::RateLimited,
+        s if s.is_server_error() => UsageErrorKind::ServerError,
+        _ => UsageErrorKind::Other,
+    };
+
+    let message = match error_message_from_body(body) {
+        Some(detail) => format!("API error: {status} - {detail}"),
+        None => format!("API error: {status}"),
+    };
+
+    UsageError {
+        status: Some(status.as_u16()),
+        retry_after_secs,
+        ..UsageError::new(kind, message)
+    }
+}
+
+/// Classify a failure that happened before a usage response could be read
+pub(super) fn classify_usage_error(err: &anyhow::Error) -> UsageError {
+    for cause in err.chain() {
+        if let Some(rejected) = cause.downcast_ref::<TokenRefreshRejected>() {
+            let status = StatusCode::from_u16(rejected.status).unwrap_or(StatusCode::BAD_REQUEST);
+            if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+                return usage_error_from_status(status, None, &rejected.body);
+            }
+            // The refresh token itself was rejected: the account needs a new login
+            return UsageError {
+                status: Some(rejected.status),
+                ..UsageError::new(UsageErrorKind::LoginRequired, format!("{err:#}"))
+            };
+        }
+
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            let kind = if e.is_decode() {
+                UsageErrorKind::Parse
+            } else {
+                UsageErrorKind::Network
+            };
+            return UsageError::new(kind, format!("{err:#}"));
+        }
+    }
+
+    UsageError::new(UsageErrorKind::Other, format!("{err:#}"))
+}
+
+/// Pull a readable message out of an error body (JSON `detail`, `error` or
+/// `message`, or short plain text)
+fn error_message_from_body(body: &str) -> Option<String> {
+    let body = body.trim();
+    if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
+        for key in ["detail", "error", "message"] {
+            match json.get(key) {
+                Some(serde_json::Value::String(s)) => return Some(s.clone()),
+                Some(obj @ serde_json::Value::Object(_)) => {
+                    if let Some(s) = obj.get("message").and_then(|v| v.as_str()) {
+                        return Some(s.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        return None;
+    }
+
+    let plain_text = !body.is_empty() && body.len() <= 200 && !body.starts_with('<');
+    plain_text.then(|| body.to_string())
+}
+
+/// Seconds to wait from a Retry-After header (delay in seconds or an HTTP date)
+pub(super) fn retry_after_secs(headers: &HeaderMap) -> Option<u64> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (date.with_timezone(&Utc) - Utc::now()).num_seconds();
+    Some(u64::try_from(secs).unwrap_or(0))
+}
+
 async fn warmup_with_chatgpt_auth(account: &StoredAccount) -> Result<()> {
     let fresh_account = ensure_chatgpt_tokens_fresh(account).await?;
     let (access_token, chatgpt_account_id) = extract_chatgpt_auth(&fresh_account)?;
 
-    let mut response = send_chatgpt_usage_request(access_token, chatgpt_account_id).await?;
+    let (mut response, mut rate_limits) =
+        send_chatgpt_usage_request(access_token, chatgpt_account_id).await?;
     if response.status() == StatusCode::UNAUTHORIZED {
         println!(
             "[Warmup] Unauthorized for account {}, refreshing token and retrying once",
@@ -131,7 +311,11 @@ async fn warmup_with_chatgpt_auth(account: &StoredAccount) -> Result<()> {
         );
         let refreshed_account = refresh_chatgpt_tokens(&fresh_account).await?;
         let (retry_token, retry_account_id) = extract_chatgpt_auth(&refreshed_account)?;
-        response = send_chatgpt_usage_request(retry_token, retry_account_id).await?;
+        (response, rate_limits) = send_chatgpt_usage_request(retry_token, retry_account_id).await?;
+    }
+
+    if let Some(snapshot) = rate_limits {
+        record_rate_limit_snapshot(&account.id, snapshot);
     }
 
     if !response.status().is_success() {
@@ -144,16 +328,21 @@ async fn warmup_with_chatgpt_auth(account: &StoredAccount) -> Result<()> {
     Ok(())
 }
 
-async fn warmup_with_api_key(api_key: &str) -> Result<()> {
-    let client = reqwest::Client::new();
+async fn warmup_with_api_key(account_id: &str, api_key: &str) -> Result<()> {
+    let endpoints = endpoint_config()?;
+    let client = http_client()?;
     let response = client
-        .get(format!("{OPENAI_API}/models"))
+        .get(format!("{}/models", endpoints.openai_api))
         .header(USER_AGENT, CODEX_USER_AGENT)
         .header(AUTHORIZATION, format!("Bearer {api_key}"))
         .send()
         .await
         .context("Failed to send API key warm-up request")?;
 
+    if let Some(snapshot) = parse_rate_limit_headers(response.headers()) {
+        record_rate_limit_snapshot(account_id, snapshot);
+    }
+
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
@@ -164,7 +353,7 @@ async fn warmup_with_api_key(api_key: &str) -> Result<()> {
     Ok(())
 }
 
-fn build_chatgpt_headers(
+pub(crate) fn build_chatgpt_headers(
     access_token: &str,
     chatgpt_account_id: Option<&str>,
 ) -> Result<HeaderMap> {
@@ -198,78 +387,247 @@ fn extract_chatgpt_auth(account: &StoredAccount) -> Result<(&str, Option<&str>)>
     }
 }
 
+/// Send the usage request; also returns the rate limits reported in its headers
 async fn send_chatgpt_usage_request(
     access_token: &str,
     chatgpt_account_id: Option<&str>,
-) -> Result<reqwest::Response> {
-    let client = reqwest::Client::new();
+) -> Result<(reqwest::Response, Option<RateLimitSnapshot>)> {
+    let endpoints = endpoint_config()?;
+    let client = http_client()?;
     let headers = build_chatgpt_headers(access_token, chatgpt_account_id)?;
-    let url = format!("{CHATGPT_BACKEND_API}/wham/usage");
+    let url = format!("{}/wham/usage", endpoints.chatgpt_backend_api);
     println!("[Usage] Requesting: {url}");
 
-    client
+    let response = client
         .get(&url)
         .headers(headers)
         .send()
         .await
-        .context("Failed to send usage request")
+        .context("Failed to send usage request")?;
+
+    let rate_limits = parse_rate_limit_headers(response.headers());
+    Ok((response, rate_limits))
 }
 
 /// Convert API response to UsageInfo
 fn convert_payload_to_usage_info(account_id: &str, payload: RateLimitStatusPayload) -> UsageInfo {
-    let (primary, secondary) = extract_rate_limits(payload.rate_limit);
-    let credits = extract_credits(payload.credits);
+    let rate_limit = payload.rate_limit.unwrap_or_default();
+    let primary = rate_limit.window("primary_window");
+    let secondary = rate_limit.window("secondary_window");
+
+    let additional_windows = rate_limit
+        .windows()
+        .into_iter()
+        .filter(|(name, _)| name != "primary_window" && name != "secondary_window")
+        .filter_map(|(name, window)| {
+            Some(UsageWindow {
+                name,
+                used_percent: window.used_percent?,
+                window_minutes: window.window_minutes(),
+                resets_at: window.resets_at(),
+            })
+        })
+        .collect();
+
+    let credits = payload.credits;
 
     UsageInfo {
-        account_id: account_id.to_string(),
-        plan_type: Some(payload.plan_type),
-        primary_used_percent: primary.as_ref().map(|w| w.used_percent),
-        primary_window_minutes: primary
-            .as_ref()
-            .and_then(|w| w.limit_window_seconds)
-            .map(|s| (i64::from(s) + 59) / 60),
-        primary_resets_at: primary.as_ref().and_then(|w| w.reset_at),
-        secondary_used_percent: secondary.as_ref().map(|w| w.used_percent),
-        secondary_window_minutes: secondary
-            .as_ref()
-            .and_then(|w| w.limit_window_seconds)
-            .map(|s| (i64::from(s) + 59) / 60),
-        secondary_resets_at: secondary.as_ref().and_then(|w| w.reset_at),
-        has_credits: credits.as_ref().map(|c| c.has_credits),
-        unlimited_credits: credits.as_ref().map(|c| c.unlimited),
-        credits_balance: credits.and_then(|c| c.balance),
-        error: None,
+        plan_type: payload.plan_type,
+        primary_used_percent: primary.as_ref().and_then(|w| w.used_percent),
+        primary_window_minutes: primary.as_ref().and_then(|w| w.window_minutes()),
+        primary_resets_at: primary.as_ref().and_then(|w| w.resets_at()),
+        secondary_used_percent: secondary.as_ref().and_then(|w| w.used_percent),
+        secondary_window_minutes: secondary.as_ref().and_then(|w| w.window_minutes()),
+        secondary_resets_at: secondary.as_ref().and_then(|w| w.resets_at()),
+        has_credits: credits.as_ref().and_then(|c| c.has_credits),
+        unlimited_credits: credits.as_ref().and_then(|c| c.unlimited),
+        credits_balance: credits.as_ref().and_then(|c| c.balance_text()),
+        additional_windows,
+        credits_extra: credits.map(|c| c.extra).unwrap_or_default(),
+        ..UsageInfo::new(account_id.to_string())
     }
 }
 
-fn extract_rate_limits(
-    rate_limit: Option<RateLimitDetails>,
-) -> (Option<RateLimitWindow>, Option<RateLimitWindow>) {
-    match rate_limit {
-        Some(details) => (details.primary_window, details.secondary_window),
-        None => (None, None),
-    }
-}
+/// Refresh all account usage, fetching up to `refresh_concurrency` accounts at once.
+/// Results keep the order of `accounts`.
+pub async fn refresh_all_usage(accounts: &[StoredAccount], force_refresh: bool) -> Vec<UsageInfo> {
+    let concurrency = refresh_concurrency();
+    println!(
+        "[Usage] Refreshing usage for {} accounts ({} at a time)",
+        accounts.len(),
+        concurrency
+    );
 
-fn extract_credits(credits: Option<CreditStatusDetails>) -> Option<CreditStatusDetails> {
-    credits
+    let results = stream::iter(accounts.iter().cloned())
+        .map(|account| async move {
+            match get_account_usage_cached(&account, force_refresh).await {
+                Ok(info) => info,
+                Err(e) => {
+                    println!("[Usage] Error for {}: {}", account.name, e);
+                    UsageInfo::failed(account.id.clone(), classify_usage_error(&e))
+                }
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    println!("[Usage] Refresh complete");
+    results
 }
 
-/// Refresh all account usage
-pub async fn refresh_all_usage(accounts: &[StoredAccount]) -> Vec<UsageInfo> {
-    println!("[Usage] Refreshing usage for {} accounts", accounts.len());
-
-    let mut results = Vec::with_capacity(accounts.len());
-    for account in accounts {
-        match get_account_usage(account).await {
-            Ok(info) => results.push(info),
-            Err(e) => {
-                println!("[Usage] Error for {}: {}", account.name, e);
-                results.push(UsageInfo::error(account.id.clone(), e.to_string()));
+/// Send warm-up requests for all accounts concurrently.
+/// Returns the IDs of the accounts whose warm-up failed.
+pub async fn warmup_all(accounts: &[StoredAccount]) -> Vec<(String, anyhow::Error)> {
+    stream::iter(accounts.iter().cloned())
+        .map(|account| async move {
+            match warmup_account(&account).await {
+                Ok(()) => None,
+                Err(e) => {
+                    println!("[Warmup] Error for {}: {}", account.name, e);
+                    Some((account.id.clone(), e))
+                }
             }
+        })
+        .buffered(refresh_concurrency())
+        .filter_map(|failed| async move { failed })
+        .collect()
+        .await
+}
+
+/// Configured number of concurrent usage requests
+fn refresh_concurrency() -> usize {
+    match load_settings() {
+        Ok(settings) => settings.usage.refresh_concurrency.max(1),
+        Err(e) => {
+            println!("[Usage] Failed to load settings, using default concurrency: {e}");
+            UsageSettings::default().refresh_concurrency
         }
     }
+}
 
-    println!("[Usage] Refresh complete");
-    results
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SnapshotWindow;
+
+    fn header_snapshot() -> RateLimitSnapshot {
+        RateLimitSnapshot {
+            captured_at: Utc::now(),
+            primary: Some(SnapshotWindow {
+                used_percent: 55.0,
+                window_minutes: Some(300),
+                resets_at: Some(1_760_791_200),
+            }),
+            secondary: Some(SnapshotWindow {
+                used_percent: 20.0,
+                window_minutes: Some(10_080),
+                resets_at: Some(1_761_283_314),
+            }),
+            has_credits: Some(true),
+            unlimited_credits: None,
+            credits_balance: Some("5.00".to_string()),
+            requests: None,
+            tokens: None,
+        }
+    }
+
+    #[test]
+    fn parses_plus_payload() {
+        let usage = usage_from_body("acc", include_str!("fixtures/usage_plus.json"), None);
+
+        assert!(usage.error.is_none());
+        assert_eq!(usage.plan_type.as_deref(), Some("plus"));
+        assert_eq!(usage.primary_used_percent, Some(42.0));
+        assert_eq!(usage.primary_window_minutes, Some(300));
+        assert_eq!(usage.primary_resets_at, Some(1_760_791_200));
+        assert_eq!(usage.secondary_used_percent, Some(7.5));
+        assert_eq!(usage.secondary_window_minutes, Some(10_080));
+        assert_eq!(usage.has_credits, Some(false));
+        assert_eq!(usage.credits_balance, None);
+        assert!(usage.additional_windows.is_empty());
+        assert_eq!(usage.raw_payload.unwrap()["plan_type"], "plus");
+    }
+
+    #[test]
+    fn keeps_named_windows_and_unknown_credit_fields() {
+        let usage = usage_from_body("acc", include_str!("fixtures/usage_extended.json"), None);
+
+        assert!(usage.error.is_none());
+        assert_eq!(usage.plan_type.as_deref(), Some("pro"));
+        assert_eq!(usage.primary_used_percent, Some(12.5));
+        assert_eq!(usage.secondary_used_percent, None);
+        assert_eq!(
+            usage.additional_windows,
+            vec![UsageWindow {
+                name: "code_review_window".to_string(),
+                used_percent: 80.0,
+                window_minutes: Some(1440),
+                resets_at: Some(1_760_850_000),
+            }]
+        );
+        assert_eq!(usage.credits_balance.as_deref(), Some("1234.5"));
+        assert!(usage.credits_extra.contains_key("approx_local_messages"));
+        assert!(usage.credits_extra.contains_key("approx_cloud_messages"));
+        assert!(usage.raw_payload.unwrap().get("promo").is_some());
+    }
+
+    #[test]
+    fn tolerates_missing_plan_and_incomplete_windows() {
+        let usage = usage_from_body("acc", include_str!("fixtures/usage_no_plan.json"), None);
+
+        assert!(usage.error.is_none());
+        assert_eq!(usage.plan_type, None);
+        assert_eq!(usage.primary_used_percent, Some(100.0));
+        assert_eq!(usage.secondary_used_percent, None);
+    }
+
+    #[test]
+    fn headers_fill_windows_missing_from_payload() {
+        let usage = usage_from_body(
+            "acc",
+            include_str!("fixtures/usage_no_plan.json"),
+            Some(header_snapshot()),
+        );
+
+        assert_eq!(usage.primary_used_percent, Some(100.0));
+        assert_eq!(usage.secondary_used_percent, Some(20.0));
+        assert_eq!(usage.secondary_window_minutes, Some(10_080));
+        assert_eq!(usage.has_credits, Some(true));
+        assert_eq!(usage.credits_balance.as_deref(), Some("5.00"));
+        assert!(usage.rate_limit_snapshot.is_some());
+    }
+
+    #[test]
+    fn malformed_payload_keeps_raw_json_and_headers() {
+        let usage = usage_from_body(
+            "acc",
+            include_str!("fixtures/usage_malformed.json"),
+            Some(header_snapshot()),
+        );
+
+        let error = usage.error_detail.as_ref().unwrap();
+        assert_eq!(error.kind, UsageErrorKind::Parse);
+        assert_eq!(usage.raw_payload.unwrap()["rate_limit"], "unavailable");
+        assert_eq!(usage.primary_used_percent, Some(55.0));
+        assert_eq!(usage.secondary_used_percent, Some(20.0));
+        assert!(usage.rate_limit_snapshot.is_some());
+    }
+
+    #[test]
+    fn non_json_body_is_kept_as_text() {
+        let body = "<html>upstream connect error</html>";
+        let usage = usage_from_body("acc", body, None);
+
+        assert_eq!(
+            usage.error_detail.map(|e| e.kind),
+            Some(UsageErrorKind::Parse)
+        );
+        assert_eq!(
+            usage.raw_payload,
+            Some(serde_json::Value::String(body.to_string()))
+        );
+        assert_eq!(usage.primary_used_percent, None);
+        assert!(usage.rate_limit_snapshot.is_none());
+    }
 }
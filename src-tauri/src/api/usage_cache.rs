@@ -0,0 +1,245 @@
+//! Cache of the last usage fetched per account
+//!
+//! Kept in memory, and optionally mirrored to ~/.codex-switcher/usage_cache.json
+//! so the last good numbers survive a restart.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+
+use crate::auth::storage::get_config_dir;
+use crate::settings::load_settings;
+use crate::types::{RateLimitSnapshot, UsageInfo, UsageSettings};
+
+#[derive(Default)]
+struct UsageCache {
+    entries: HashMap<String, UsageInfo>,
+    /// Whether usage_cache.json was merged in; it is read the first time the
+    /// cache is used with persistence enabled, which may be after startup
+    loaded_from_disk: bool,
+}
+
+/// Last successful usage per account ID; `None` until first use
+static CACHE: Mutex<Option<UsageCache>> = Mutex::new(None);
+
+/// A concurrent refresh stores many accounts in quick succession; changes
+/// within this delay are written to disk together
+const SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Set while a background save is scheduled
+static SAVE_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// Serializes writes so an older snapshot never overwrites a newer one
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Get the path to usage_cache.json
+pub fn get_usage_cache_file() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("usage_cache.json"))
+}
+
+fn usage_settings() -> UsageSettings {
+    match load_settings() {
+        Ok(settings) => settings.usage,
+        Err(e) => {
+            println!("[Usage] Failed to load settings, using default cache settings: {e}");
+            UsageSettings::default()
+        }
+    }
+}
+
+/// Run `f` on the cache, loading it from disk first if persistence is enabled
+fn with_cache<T>(
+    settings: &UsageSettings,
+    f: impl FnOnce(&mut HashMap<String, UsageInfo>) -> T,
+) -> T {
+    let mut cache = CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(UsageCache::default);
+    if settings.persist_cache && !cache.loaded_from_disk {
+        cache.loaded_from_disk = true;
+        match load_cache_file() {
+            // Usage fetched in this session is newer than the file
+            Ok(stored) => {
+                for (account_id, usage) in stored {
+                    cache.entries.entry(account_id).or_insert(usage);
+                }
+            }
+            Err(e) => println!("[Usage] Failed to load usage cache: {e}"),
+        }
+    }
+    f(&mut cache.entries)
+}
+
+fn load_cache_file() -> Result<HashMap<String, UsageInfo>> {
+    let path = get_usage_cache_file()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read usage cache: {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse usage cache: {}", path.display()))
+}
+
+fn save_cache_file(entries: &HashMap<String, UsageInfo>) -> Result<()> {
+    let path = get_usage_cache_file()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
+    }
+
+    let content =
+        serde_json::to_string_pretty(entries).context("Failed to serialize usage cache")?;
+    write_private_file(&path, content.as_bytes())
+        .with_context(|| format!("Failed to write usage cache: {}", path.display()))
+}
+
+/// Write a file only the current user can read (it holds plan and credit
+/// details). The content goes to a temporary file created with mode 0600,
+/// which then replaces `path`, so it is never readable by others.
+fn write_private_file(path: &Path, content: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("json.tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp_path)?;
+    // A leftover temporary file keeps the mode it was created with
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Save the cache in the background, batching changes made within `SAVE_DELAY`
+fn schedule_save() {
+    if SAVE_SCHEDULED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        std::thread::sleep(SAVE_DELAY);
+        let _save = SAVE_LOCK.lock().unwrap();
+        // Changes from here on schedule another save
+        SAVE_SCHEDULED.store(false, Ordering::SeqCst);
+        let entries = CACHE
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|cache| cache.entries.clone())
+            .unwrap_or_default();
+        if let Err(e) = save_cache_file(&entries) {
+            println!("[Usage] Failed to save usage cache: {e}");
+        }
+    });
+}
+
+/// Last good usage for an account, regardless of age
+pub fn last_cached_usage(account_id: &str) -> Option<UsageInfo> {
+    let settings = usage_settings();
+    with_cache(&settings, |entries| entries.get(account_id).cloned())
+}
+
+/// Cached usage for an account if it is younger than the configured TTL
+pub fn fresh_cached_usage(account_id: &str) -> Option<UsageInfo> {
+    let settings = usage_settings();
+    let ttl = i64::try_from(settings.cache_ttl_secs)
+        .ok()
+        .and_then(Duration::try_seconds)
+        .unwrap_or(Duration::MAX);
+
+    with_cache(&settings, |entries| {
+        entries
+            .get(account_id)
+            .filter(|usage| {
+                usage
+                    .fetched_at
+                    .is_some_and(|fetched_at| Utc::now() - fetched_at < ttl)
+            })
+            .cloned()
+    })
+}
+
+/// Remember a successful usage fetch
+pub fn store_cached_usage(usage: &UsageInfo) {
+    if usage.error.is_some() {
+        return;
+    }
+
+    let settings = usage_settings();
+    with_cache(&settings, |entries| {
+        entries.insert(usage.account_id.clone(), usage.clone());
+        if settings.persist_cache {
+            schedule_save();
+        }
+    });
+}
+
+/// Attach rate limits seen on another request (e.g. a warm-up) to the cached usage
+pub fn record_rate_limit_snapshot(account_id: &str, snapshot: RateLimitSnapshot) {
+    let settings = usage_settings();
+    with_cache(&settings, |entries| {
+        if let Some(usage) = entries.get_mut(account_id) {
+            usage.rate_limit_snapshot = Some(snapshot);
+            if settings.persist_cache {
+                schedule_save();
+            }
+        }
+    });
+}
+
+/// Forget the cached usage of an account (e.g. after it was deleted)
+pub fn remove_cached_usage(account_id: &str) {
+    let settings = usage_settings();
+    with_cache(&settings, |entries| {
+        if entries.remove(account_id).is_some() && settings.persist_cache {
+            schedule_save();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn private_file_is_never_world_readable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("usage-cache-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("usage_cache.json");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::write(path.with_extension("json.tmp"), "stale").unwrap();
+
+        write_private_file(&path, b"{}").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        let content = fs::read_to_string(&path).unwrap();
+        let tmp_left = path.with_extension("json.tmp").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(mode, 0o600);
+        assert_eq!(content, "{}");
+        assert!(!tmp_left);
+    }
+}
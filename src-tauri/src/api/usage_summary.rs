@@ -0,0 +1,231 @@
+//! Aggregate usage across all accounts
+
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+
+use super::credits::parse_credits_balance;
+use crate::types::{
+    CreditsTotal, PlanUsageSummary, StoredAccount, UsageInfo, UsageSummary, WindowSummary,
+};
+
+/// Usage at or above this percentage counts as exhausted
+const EXHAUSTED_PERCENT: f64 = 100.0;
+
+/// Look-ahead used for `usable_next_hour_accounts`
+const NEXT_HOUR_SECS: i64 = 3600;
+
+/// Build the summary from the latest usage of each account
+pub fn summarize_usage(accounts: &[StoredAccount], usage: &[UsageInfo]) -> UsageSummary {
+    let now = Utc::now().timestamp();
+    let mut primary = WindowAccumulator::default();
+    let mut secondary = WindowAccumulator::default();
+    let mut plans: BTreeMap<String, PlanUsageSummary> = BTreeMap::new();
+
+    let mut accounts_with_data = 0;
+    let mut exhausted_accounts = 0;
+    let mut usable_next_hour_accounts = 0;
+    let mut credits: BTreeMap<Option<String>, CreditsTotal> = BTreeMap::new();
+    let mut unlimited_credit_accounts = 0;
+
+    for account in accounts {
+        let Some(info) = usage.iter().find(|u| u.account_id == account.id) else {
+            continue;
+        };
+        let has_data = info.primary_used_percent.is_some() || info.secondary_used_percent.is_some();
+        if has_data {
+            accounts_with_data += 1;
+        }
+
+        primary.add(info.primary_used_percent, info.primary_resets_at);
+        secondary.add(info.secondary_used_percent, info.secondary_resets_at);
+
+        let windows = [
+            (info.primary_used_percent, info.primary_resets_at),
+            (info.secondary_used_percent, info.secondary_resets_at),
+        ];
+        let exhausted_windows: Vec<Option<i64>> = windows
+            .iter()
+            .filter(|(used, _)| used.is_some_and(|u| u >= EXHAUSTED_PERCENT))
+            .map(|(_, resets_at)| *resets_at)
+            .collect();
+        let exhausted = !exhausted_windows.is_empty();
+        if exhausted {
+            exhausted_accounts += 1;
+        }
+
+        // Usable within the hour: not exhausted, or every exhausted window resets by then
+        let usable_next_hour = has_data
+            && exhausted_windows
+                .iter()
+                .all(|resets_at| resets_at.is_some_and(|r| r - now <= NEXT_HOUR_SECS));
+        if usable_next_hour {
+            usable_next_hour_accounts += 1;
+        }
+
+        if info.unlimited_credits == Some(true) {
+            unlimited_credit_accounts += 1;
+        } else if let Some(balance) = info
+            .credits_balance
+            .as_deref()
+            .and_then(parse_credits_balance)
+        {
+            // Amounts in different currencies are not added up
+            let total = credits
+                .entry(balance.currency.clone())
+                .or_insert_with(|| CreditsTotal {
+                    currency: balance.currency,
+                    amount: 0.0,
+                    accounts: 0,
+                });
+            total.amount += balance.amount;
+            total.accounts += 1;
+        }
+
+        let plan_type = account
+            .plan_type
+            .clone()
+            .or_else(|| info.plan_type.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+        let plan = plans
+            .entry(plan_type.clone())
+            .or_insert_with(|| PlanUsageSummary {
+                plan_type,
+                accounts: 0,
+                exhausted_accounts: 0,
+                primary_remaining_percent: 0.0,
+                secondary_remaining_percent: 0.0,
+            });
+        plan.accounts += 1;
+        if exhausted {
+            plan.exhausted_accounts += 1;
+        }
+        plan.primary_remaining_percent += remaining(info.primary_used_percent);
+        plan.secondary_remaining_percent += remaining(info.secondary_used_percent);
+    }
+
+    let primary = primary.finish();
+    let secondary = secondary.finish();
+    let next_reset_at = [primary.next_reset_at, secondary.next_reset_at]
+        .into_iter()
+        .flatten()
+        .min();
+
+    UsageSummary {
+        total_accounts: accounts.len(),
+        accounts_with_data,
+        exhausted_accounts,
+        usable_next_hour_accounts,
+        primary,
+        secondary,
+        next_reset_at,
+        credits_totals: credits.into_values().collect(),
+        unlimited_credit_accounts,
+        plans: plans.into_values().collect(),
+        generated_at: Utc::now(),
+    }
+}
+
+#[derive(Default)]
+struct WindowAccumulator {
+    accounts: usize,
+    used_total: f64,
+    exhausted_accounts: usize,
+    next_reset_at: Option<i64>,
+}
+
+impl WindowAccumulator {
+    fn add(&mut self, used_percent: Option<f64>, resets_at: Option<i64>) {
+        let Some(used) = used_percent else {
+            return;
+        };
+        self.accounts += 1;
+        self.used_total += used.clamp(0.0, 100.0);
+        if used >= EXHAUSTED_PERCENT {
+            self.exhausted_accounts += 1;
+        }
+        if let Some(resets_at) = resets_at {
+            self.next_reset_at = Some(self.next_reset_at.map_or(resets_at, |n| n.min(resets_at)));
+        }
+    }
+
+    fn finish(self) -> WindowSummary {
+        WindowSummary {
+            accounts: self.accounts,
+            remaining_percent_total: self.accounts as f64 * 100.0 - self.used_total,
+            average_used_percent: (self.accounts > 0)
+                .then(|| self.used_total / self.accounts as f64),
+            exhausted_accounts: self.exhausted_accounts,
+            next_reset_at: self.next_reset_at,
+        }
+    }
+}
+
+fn remaining(used_percent: Option<f64>) -> f64 {
+    used_percent.map_or(0.0, |used| 100.0 - used.clamp(0.0, 100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_credits(balances: &[Option<&str>]) -> (Vec<StoredAccount>, Vec<UsageInfo>) {
+        balances
+            .iter()
+            .enumerate()
+            .map(|(i, balance)| {
+                let account = StoredAccount::new_api_key(format!("Key {i}"), "sk".to_string());
+                let mut usage = UsageInfo::new(account.id.clone());
+                usage.credits_balance = balance.map(String::from);
+                (account, usage)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn credits_are_totalled_per_currency() {
+        let (accounts, usage) = with_credits(&[
+            Some("$10.00"),
+            Some("€5"),
+            Some("$2.50"),
+            Some("7"),
+            Some("n/a"),
+            None,
+        ]);
+
+        let summary = summarize_usage(&accounts, &usage);
+
+        assert_eq!(
+            summary.credits_totals,
+            vec![
+                CreditsTotal {
+                    currency: None,
+                    amount: 7.0,
+                    accounts: 1,
+                },
+                CreditsTotal {
+                    currency: Some("EUR".to_string()),
+                    amount: 5.0,
+                    accounts: 1,
+                },
+                CreditsTotal {
+                    currency: Some("USD".to_string()),
+                    amount: 12.5,
+                    accounts: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unlimited_credits_are_counted_not_summed() {
+        let (accounts, mut usage) = with_credits(&[Some("$10.00"), Some("$99.00")]);
+        usage[1].unlimited_credits = Some(true);
+
+        let summary = summarize_usage(&accounts, &usage);
+
+        assert_eq!(summary.unlimited_credit_accounts, 1);
+        assert_eq!(summary.credits_totals.len(), 1);
+        assert_eq!(summary.credits_totals[0].amount, 10.0);
+    }
+}
@@ -0,0 +1,144 @@
+//! ChatGPT workspaces (personal and team accounts) available to a login
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use super::http_client;
+use super::usage::build_chatgpt_headers;
+use crate::auth::{ensure_chatgpt_tokens_fresh, IdTokenClaims};
+use crate::settings::endpoint_config;
+use crate::types::{AccountWorkspace, AuthData, StoredAccount};
+
+/// Fetch the workspaces the login can switch between.
+///
+/// These are ChatGPT account IDs, which is what the `chatgpt-account-id`
+/// header expects. The ID token's `organizations` claim lists API platform
+/// organizations (`org-…`) instead, which the ChatGPT backend does not accept.
+pub async fn fetch_chatgpt_workspaces(account: &StoredAccount) -> Result<Vec<AccountWorkspace>> {
+    let account = ensure_chatgpt_tokens_fresh(account).await?;
+    let AuthData::ChatGPT {
+        id_token,
+        access_token,
+        ..
+    } = &account.auth_data
+    else {
+        anyhow::bail!("Workspaces are only available for ChatGPT accounts");
+    };
+
+    let endpoints = endpoint_config()?;
+    let url = format!(
+        "{}/accounts/check/v4-2023-04-27",
+        endpoints.chatgpt_backend_api
+    );
+    let response = http_client()?
+        .get(&url)
+        .headers(build_chatgpt_headers(access_token, None)?)
+        .send()
+        .await
+        .context("Failed to send workspaces request")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let snippet: String = body.chars().take(200).collect();
+        anyhow::bail!("Workspaces request failed: {status} - {snippet}");
+    }
+
+    let body: Value = response
+        .json()
+        .await
+        .context("Failed to parse workspaces response")?;
+    let default_id = IdTokenClaims::parse(id_token).and_then(|c| c.chatgpt_account_id);
+    Ok(parse_accounts_check(&body, default_id.as_deref()))
+}
+
+/// Workspaces from an accounts check response, in the backend's display order
+fn parse_accounts_check(body: &Value, default_id: Option<&str>) -> Vec<AccountWorkspace> {
+    let Some(accounts) = body.get("accounts").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let ordering: Vec<&str> = body
+        .get("account_ordering")
+        .and_then(Value::as_array)
+        .map(|ids| ids.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut workspaces: Vec<AccountWorkspace> = Vec::new();
+    for (key, entry) in accounts {
+        let Some(account) = entry.get("account") else {
+            continue;
+        };
+        let field = |name: &str| account.get(name).and_then(Value::as_str);
+
+        let id = field("account_id").unwrap_or(key);
+        // The default account is also listed under a "default" alias
+        if id == "default" || workspaces.iter().any(|w| w.id == id) {
+            continue;
+        }
+
+        let title = field("name")
+            .map(str::to_string)
+            .or_else(|| (field("structure") == Some("personal")).then(|| "Personal".to_string()));
+        workspaces.push(AccountWorkspace {
+            id: id.to_string(),
+            title,
+            role: field("account_user_role").map(str::to_string),
+            is_default: default_id == Some(id),
+        });
+    }
+
+    let position = |id: &str| ordering.iter().position(|o| *o == id).unwrap_or(usize::MAX);
+    workspaces.sort_by_key(|w| position(&w.id));
+    workspaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_personal_and_team_workspaces_in_order() {
+        let body = json!({
+            "accounts": {
+                "default": {
+                    "account": { "account_id": "11111111-personal", "structure": "personal", "name": null }
+                },
+                "11111111-personal": {
+                    "account": { "account_id": "11111111-personal", "structure": "personal", "name": null, "account_user_role": "account-owner" }
+                },
+                "22222222-team": {
+                    "account": { "account_id": "22222222-team", "structure": "workspace", "name": "Acme", "account_user_role": "standard-user" }
+                }
+            },
+            "account_ordering": ["22222222-team", "11111111-personal"]
+        });
+
+        let workspaces = parse_accounts_check(&body, Some("11111111-personal"));
+        assert_eq!(
+            workspaces,
+            vec![
+                AccountWorkspace {
+                    id: "22222222-team".to_string(),
+                    title: Some("Acme".to_string()),
+                    role: Some("standard-user".to_string()),
+                    is_default: false,
+                },
+                AccountWorkspace {
+                    id: "11111111-personal".to_string(),
+                    title: Some("Personal".to_string()),
+                    role: Some("account-owner".to_string()),
+                    is_default: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_unexpected_shapes() {
+        assert!(parse_accounts_check(&json!({}), None).is_empty());
+        assert!(parse_accounts_check(&json!({ "accounts": [] }), None).is_empty());
+        let body = json!({ "accounts": { "x": { "features": [] } } });
+        assert!(parse_accounts_check(&body, None).is_empty());
+    }
+}
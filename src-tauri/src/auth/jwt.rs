@@ -0,0 +1,300 @@
+//! JWT claim parsing for ChatGPT ID and access tokens
+//!
+//! Tokens are decoded without signature validation; the claims are only used
+//! for display and bookkeeping, never for authorization decisions.
+
+use base64::Engine;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+use crate::types::AccountWorkspace;
+
+/// Claims from a ChatGPT ID token
+#[derive(Debug, Clone, Default)]
+pub struct IdTokenClaims {
+    pub email: Option<String>,
+    pub plan_type: Option<String>,
+    /// Default workspace the login was issued for
+    pub chatgpt_account_id: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub issued_at: Option<DateTime<Utc>>,
+    pub auth_time: Option<DateTime<Utc>>,
+    pub subscription_active_until: Option<DateTime<Utc>>,
+}
+
+/// Claims from a ChatGPT access token
+#[derive(Debug, Clone, Default)]
+pub struct AccessTokenClaims {
+    pub email: Option<String>,
+    pub plan_type: Option<String>,
+    pub chatgpt_account_id: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub issued_at: Option<DateTime<Utc>>,
+}
+
+// Claims are decoded field by field: a field with an unexpected type is
+// dropped instead of failing the whole token.
+
+#[derive(Debug, Default, Deserialize)]
+struct RawClaims {
+    #[serde(default, deserialize_with = "lenient")]
+    email: Option<String>,
+    #[serde(default, deserialize_with = "lenient_seconds")]
+    exp: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_seconds")]
+    iat: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_seconds")]
+    auth_time: Option<i64>,
+    #[serde(
+        default,
+        rename = "https://api.openai.com/auth",
+        deserialize_with = "lenient"
+    )]
+    auth: Option<RawAuthClaims>,
+    #[serde(
+        default,
+        rename = "https://api.openai.com/profile",
+        deserialize_with = "lenient"
+    )]
+    profile: Option<RawProfileClaims>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawAuthClaims {
+    #[serde(default, deserialize_with = "lenient")]
+    chatgpt_plan_type: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    chatgpt_account_id: Option<String>,
+    #[serde(default)]
+    chatgpt_subscription_active_until: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawProfileClaims {
+    #[serde(default, deserialize_with = "lenient")]
+    email: Option<String>,
+}
+
+/// Deserialize a value, treating one of the wrong type as absent
+fn lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).ok())
+}
+
+/// Unix seconds sent as an integer, a float or a numeric string
+fn lenient_seconds<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds = match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+        serde_json::Value::String(s) => s.trim().parse::<f64>().ok().map(|f| f as i64),
+        _ => None,
+    };
+    Ok(seconds)
+}
+
+impl IdTokenClaims {
+    /// Parse the claims of an ID token. Returns `None` for malformed tokens.
+    pub fn parse(token: &str) -> Option<Self> {
+        let raw = decode_claims(token)?;
+        let auth = raw.auth.unwrap_or_default();
+
+        Some(Self {
+            email: raw.email,
+            plan_type: auth.chatgpt_plan_type,
+            chatgpt_account_id: auth.chatgpt_account_id,
+            expires_at: raw.exp.and_then(timestamp),
+            issued_at: raw.iat.and_then(timestamp),
+            auth_time: raw.auth_time.and_then(timestamp),
+            subscription_active_until: auth
+                .chatgpt_subscription_active_until
+                .as_ref()
+                .and_then(claim_date),
+        })
+    }
+}
+
+impl IdTokenClaims {
+    /// The ChatGPT workspace the login was issued for. The token's
+    /// `organizations` are API platform orgs and cannot be sent as a ChatGPT
+    /// account ID, so they are not parsed.
+    pub fn default_workspace(&self) -> Option<AccountWorkspace> {
+        Some(AccountWorkspace {
+            id: self.chatgpt_account_id.clone()?,
+            title: None,
+            role: None,
+            is_default: true,
+        })
+    }
+}
+
+impl AccessTokenClaims {
+    /// Parse the claims of an access token. Returns `None` for malformed tokens.
+    pub fn parse(token: &str) -> Option<Self> {
+        let raw = decode_claims(token)?;
+        let auth = raw.auth.unwrap_or_default();
+
+        Some(Self {
+            email: raw.email.or(raw.profile.and_then(|p| p.email)),
+            plan_type: auth.chatgpt_plan_type,
+            chatgpt_account_id: auth.chatgpt_account_id,
+            expires_at: raw.exp.and_then(timestamp),
+            issued_at: raw.iat.and_then(timestamp),
+        })
+    }
+}
+
+/// Token timestamps shown for a ChatGPT account
+#[derive(Debug, Clone, Default)]
+pub struct TokenTimes {
+    pub access_token_expires_at: Option<DateTime<Utc>>,
+    /// When the access token was issued
+    pub access_token_issued_at: Option<DateTime<Utc>>,
+    /// When the login happened, which is when the refresh token was issued
+    pub refresh_token_issued_at: Option<DateTime<Utc>>,
+    pub subscription_active_until: Option<DateTime<Utc>>,
+}
+
+impl TokenTimes {
+    /// Read the timestamps from a token pair; unparseable tokens yield `None`s
+    pub fn from_tokens(id_token: &str, access_token: &str) -> Self {
+        let id_claims = IdTokenClaims::parse(id_token).unwrap_or_default();
+        let access_claims = AccessTokenClaims::parse(access_token).unwrap_or_default();
+
+        Self {
+            access_token_expires_at: access_claims.expires_at,
+            access_token_issued_at: access_claims.issued_at,
+            refresh_token_issued_at: id_claims.auth_time.or(id_claims.issued_at),
+            subscription_active_until: id_claims.subscription_active_until,
+        }
+    }
+}
+
+/// Decode the payload (second part) of a JWT
+fn decode_claims(token: &str) -> Option<RawClaims> {
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let encoded = parts[1].trim_end_matches('=');
+    let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .or_else(|_| base64::engine::general_purpose::STANDARD_NO_PAD.decode(encoded))
+        .ok()?;
+    match serde_json::from_slice(&payload).ok()? {
+        claims @ serde_json::Value::Object(_) => serde_json::from_value(claims).ok(),
+        _ => None,
+    }
+}
+
+fn timestamp(seconds: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds, 0).single()
+}
+
+/// Subscription dates appear either as RFC 3339 strings or as Unix seconds
+fn claim_date(value: &serde_json::Value) -> Option<DateTime<Utc>> {
+    match value {
+        serde_json::Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|d| d.with_timezone(&Utc)),
+        serde_json::Value::Number(n) => n.as_i64().and_then(timestamp),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn token(claims: serde_json::Value) -> String {
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        format!(
+            "{}.{}.sig",
+            engine.encode(r#"{"alg":"RS256"}"#),
+            engine.encode(claims.to_string())
+        )
+    }
+
+    #[test]
+    fn parses_id_token_claims() {
+        let claims = IdTokenClaims::parse(&token(json!({
+            "email": "dev@example.com",
+            "exp": 1_760_000_000,
+            "https://api.openai.com/auth": {
+                "chatgpt_plan_type": "plus",
+                "chatgpt_account_id": "acct-1",
+                "chatgpt_subscription_active_until": "2026-11-01T00:00:00Z"
+            }
+        })))
+        .unwrap();
+
+        assert_eq!(claims.email.as_deref(), Some("dev@example.com"));
+        assert_eq!(claims.plan_type.as_deref(), Some("plus"));
+        assert_eq!(claims.chatgpt_account_id.as_deref(), Some("acct-1"));
+        assert_eq!(claims.expires_at.unwrap().timestamp(), 1_760_000_000);
+        assert!(claims.subscription_active_until.is_some());
+    }
+
+    #[test]
+    fn tolerates_null_organizations_and_odd_types() {
+        let claims = IdTokenClaims::parse(&token(json!({
+            "email": "dev@example.com",
+            "exp": 1_760_000_000.75,
+            "iat": "1759990000",
+            "https://api.openai.com/auth": {
+                "chatgpt_plan_type": { "name": "pro" },
+                "chatgpt_account_id": "acct-1",
+                "organizations": null
+            }
+        })))
+        .unwrap();
+
+        assert_eq!(claims.email.as_deref(), Some("dev@example.com"));
+        assert_eq!(claims.plan_type, None);
+        assert_eq!(claims.chatgpt_account_id.as_deref(), Some("acct-1"));
+        assert_eq!(claims.expires_at.unwrap().timestamp(), 1_760_000_000);
+        assert_eq!(claims.issued_at.unwrap().timestamp(), 1_759_990_000);
+    }
+
+    #[test]
+    fn tolerates_malformed_auth_claim() {
+        let claims = AccessTokenClaims::parse(&token(json!({
+            "exp": 1_760_000_000,
+            "https://api.openai.com/auth": "not an object",
+            "https://api.openai.com/profile": { "email": "dev@example.com" }
+        })))
+        .unwrap();
+
+        assert_eq!(claims.email.as_deref(), Some("dev@example.com"));
+        assert_eq!(claims.chatgpt_account_id, None);
+        assert_eq!(claims.expires_at.unwrap().timestamp(), 1_760_000_000);
+    }
+
+    #[test]
+    fn rejects_malformed_tokens() {
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+        assert!(IdTokenClaims::parse("").is_none());
+        assert!(IdTokenClaims::parse("only.two").is_none());
+        assert!(IdTokenClaims::parse("a.b.c.d").is_none());
+        assert!(IdTokenClaims::parse("header.!!!.sig").is_none());
+        assert!(IdTokenClaims::parse(&format!("h.{}.s", engine.encode("not json"))).is_none());
+        assert!(IdTokenClaims::parse(&format!("h.{}.s", engine.encode("[1, 2]"))).is_none());
+        assert!(AccessTokenClaims::parse("only.two").is_none());
+    }
+
+    #[test]
+    fn accepts_padded_payload() {
+        let engine = base64::engine::general_purpose::URL_SAFE;
+        let payload = engine.encode(json!({ "email": "a@b.co" }).to_string());
+        let claims = IdTokenClaims::parse(&format!("h.{payload}.s")).unwrap();
+        assert_eq!(claims.email.as_deref(), Some("a@b.co"));
+    }
+}
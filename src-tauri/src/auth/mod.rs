@@ -1,11 +1,15 @@
 //! Authentication module
 
+pub mod jwt;
 pub mod oauth_server;
+pub mod revoke;
 pub mod storage;
 pub mod switcher;
 pub mod token_refresh;
 
+pub use jwt::*;
 pub use oauth_server::*;
+pub use revoke::*;
 pub use storage::*;
 pub use switcher::*;
 pub use token_refresh::*;
@@ -2,21 +2,28 @@
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::thread;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use base64::Engine;
 use rand::RngCore;
 use sha2::{Digest, Sha256};
-use tiny_http::{Header, Request, Response, Server};
-use tokio::sync::oneshot;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{oneshot, Notify};
 
-use crate::types::{OAuthLoginInfo, StoredAccount};
+use super::IdTokenClaims;
+use crate::api::http_client;
+use crate::settings::{endpoint_config, load_settings};
+use crate::types::{
+    DeviceLoginInfo, EndpointConfig, OAuthLoginInfo, OAuthProgressStage, StoredAccount,
+};
 
-const DEFAULT_ISSUER: &str = "https://auth.openai.com";
-const CLIENT_ID: &str = "app_EMoamEEZ73f0CkXaXp7hrann";
 const DEFAULT_PORT: u16 = 1455; // Same as official Codex
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_REQUEST_HEAD_BYTES: usize = 16 * 1024;
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const DEFAULT_DEVICE_POLL_INTERVAL_SECS: u64 = 5;
 
 /// PKCE codes for OAuth
 #[derive(Debug, Clone)]
@@ -93,7 +100,7 @@ async fn exchange_code_for_tokens(
     pkce: &PkceCodes,
     code: &str,
 ) -> Result<TokenResponse> {
-    let client = reqwest::Client::new();
+    let client = http_client()?;
 
     let body = format!(
         "grant_type=authorization_code&code={}&redirect_uri={}&client_id={}&code_verifier={}",
@@ -124,53 +131,117 @@ async fn exchange_code_for_tokens(
     Ok(tokens)
 }
 
-/// Parse claims from JWT ID token
-fn parse_id_token_claims(id_token: &str) -> (Option<String>, Option<String>, Option<String>) {
-    let parts: Vec<&str> = id_token.split('.').collect();
-    if parts.len() != 3 {
-        return (None, None, None);
-    }
+/// Create a ChatGPT account from a token response, using the ID token claims
+fn account_from_tokens(account_name: String, tokens: TokenResponse) -> StoredAccount {
+    let claims = IdTokenClaims::parse(&tokens.id_token).unwrap_or_default();
+    let workspaces = claims.default_workspace().into_iter().collect();
+
+    let mut account = StoredAccount::new_chatgpt(
+        account_name,
+        claims.email,
+        claims.plan_type,
+        tokens.id_token,
+        tokens.access_token,
+        tokens.refresh_token,
+        claims.chatgpt_account_id,
+    );
+    account.workspaces = workspaces;
+    account.last_refresh_at = Some(chrono::Utc::now());
+    account.refresh_token_issued_at = account.last_refresh_at;
+    account
+}
 
-    let payload = match base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(parts[1]) {
-        Ok(bytes) => bytes,
-        Err(_) => return (None, None, None),
-    };
+/// OAuth login flow result
+pub struct OAuthLoginResult {
+    pub account: StoredAccount,
+}
 
-    let json: serde_json::Value = match serde_json::from_slice(&payload) {
-        Ok(v) => v,
-        Err(_) => return (None, None, None),
-    };
+/// Reasons a login stopped without succeeding or failing on its own
+#[derive(Debug, thiserror::Error)]
+pub enum LoginAborted {
+    #[error("OAuth login cancelled")]
+    Cancelled,
+    #[error("OAuth login timed out")]
+    TimedOut,
+}
+
+/// Callback invoked as a login flow makes progress (used to emit UI events)
+pub type LoginProgress = Arc<dyn Fn(OAuthProgressStage, Option<&str>) + Send + Sync>;
+
+/// Cancellation handle shared between a login flow and whoever started it.
+/// Cancelling wakes the flow immediately instead of waiting for its next poll.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationInner>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
 
-    let email = json.get("email").and_then(|v| v.as_str()).map(String::from);
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    let auth_claims = json.get("https://api.openai.com/auth");
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
 
-    let plan_type = auth_claims
-        .and_then(|auth| auth.get("chatgpt_plan_type"))
-        .and_then(|v| v.as_str())
-        .map(String::from);
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
 
-    let account_id = auth_claims
-        .and_then(|auth| auth.get("chatgpt_account_id"))
-        .and_then(|v| v.as_str())
-        .map(String::from);
+    /// Resolves once `cancel` has been called
+    pub async fn cancelled(&self) {
+        loop {
+            // Register before checking the flag so a concurrent cancel is not missed
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
 
-    (email, plan_type, account_id)
+/// Report the final stage of a login flow based on its result
+fn report_outcome(progress: &LoginProgress, result: &Result<OAuthLoginResult>) {
+    match result {
+        Ok(_) => progress(OAuthProgressStage::Completed, None),
+        Err(e) => match e.downcast_ref::<LoginAborted>() {
+            Some(LoginAborted::Cancelled) => progress(OAuthProgressStage::Cancelled, None),
+            Some(LoginAborted::TimedOut) => progress(OAuthProgressStage::TimedOut, None),
+            None => progress(OAuthProgressStage::Failed, Some(&e.to_string())),
+        },
+    }
 }
 
-/// OAuth login flow result
-pub struct OAuthLoginResult {
-    pub account: StoredAccount,
+/// Everything the callback handler needs to finish a login
+struct CallbackContext {
+    pkce: PkceCodes,
+    expected_state: String,
+    redirect_uri: String,
+    endpoints: EndpointConfig,
+    account_name: String,
 }
 
-/// Start the OAuth login flow
+/// Start the OAuth login flow.
+/// The callback server runs as a task on the current (app) runtime.
 pub async fn start_oauth_login(
     account_name: String,
+    open_browser: bool,
+    progress: LoginProgress,
 ) -> Result<(
     OAuthLoginInfo,
     oneshot::Receiver<Result<OAuthLoginResult>>,
-    Arc<AtomicBool>,
+    CancellationToken,
 )> {
+    let endpoints = endpoint_config()?;
+    let timeout = Duration::from_secs(load_settings()?.login.callback_timeout_secs);
     let pkce = generate_pkce();
     let state = generate_state();
 
@@ -178,13 +249,13 @@ pub async fn start_oauth_login(
     println!("[OAuth] PKCE challenge: {}", &pkce.code_challenge[..20]);
 
     // Try official default port first; fall back to a random free port if it is busy.
-    let server = match Server::http(format!("127.0.0.1:{DEFAULT_PORT}")) {
-        Ok(server) => server,
+    let listener = match TcpListener::bind(("127.0.0.1", DEFAULT_PORT)).await {
+        Ok(listener) => listener,
         Err(default_err) => {
             println!(
                 "[OAuth] Default callback port {DEFAULT_PORT} unavailable ({default_err}), using a random local port"
             );
-            Server::http("127.0.0.1:0").map_err(|fallback_err| {
+            TcpListener::bind(("127.0.0.1", 0)).await.map_err(|fallback_err| {
                 anyhow::anyhow!(
                     "Failed to start OAuth server: default port {DEFAULT_PORT} error: {default_err}; fallback error: {fallback_err}"
                 )
@@ -192,118 +263,161 @@ pub async fn start_oauth_login(
         }
     };
 
-    let actual_port = match server.server_addr().to_ip() {
-        Some(addr) => addr.port(),
-        None => anyhow::bail!("Failed to determine server port"),
-    };
+    let actual_port = listener
+        .local_addr()
+        .context("Failed to determine server port")?
+        .port();
 
     let redirect_uri = format!("http://localhost:{actual_port}/auth/callback");
-    let auth_url = build_authorize_url(DEFAULT_ISSUER, CLIENT_ID, &redirect_uri, &pkce, &state);
+    let auth_url = build_authorize_url(
+        &endpoints.issuer,
+        &endpoints.client_id,
+        &redirect_uri,
+        &pkce,
+        &state,
+    );
 
     println!("[OAuth] Server started on port {actual_port}");
     println!("[OAuth] Redirect URI: {redirect_uri}");
     println!("[OAuth] Auth URL: {auth_url}");
 
     let login_info = OAuthLoginInfo {
+        session_id: String::new(),
         auth_url: auth_url.clone(),
         callback_port: actual_port,
     };
 
+    progress(OAuthProgressStage::Listening, None);
+
+    if open_browser {
+        match webbrowser::open(&auth_url) {
+            Ok(()) => progress(OAuthProgressStage::BrowserOpened, None),
+            Err(e) => println!("[OAuth] Failed to open browser: {e}"),
+        }
+    }
+
     // Create a channel for the result
     let (tx, rx) = oneshot::channel();
-    let cancelled = Arc::new(AtomicBool::new(false));
-
-    // Spawn the server in a background thread
-    let server = Arc::new(server);
-    let pkce_clone = pkce.clone();
-    let state_clone = state.clone();
-    let cancelled_clone = cancelled.clone();
-
-    thread::spawn(move || {
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        let result = runtime.block_on(run_oauth_server(
-            server,
-            pkce_clone,
-            state_clone,
-            redirect_uri,
-            account_name,
-            cancelled_clone,
-        ));
+    let cancel = CancellationToken::new();
+
+    let ctx = CallbackContext {
+        pkce,
+        expected_state: state,
+        redirect_uri,
+        endpoints,
+        account_name,
+    };
+    let cancel_clone = cancel.clone();
+
+    tokio::spawn(async move {
+        let result = run_oauth_server(listener, ctx, cancel_clone, timeout, &progress).await;
+        report_outcome(&progress, &result);
         let _ = tx.send(result);
     });
 
-    Ok((login_info, rx, cancelled))
+    Ok((login_info, rx, cancel))
 }
 
-/// Run the OAuth callback server
+/// Run the OAuth callback server until a login completes, fails, is cancelled or times out
 async fn run_oauth_server(
-    server: Arc<Server>,
-    pkce: PkceCodes,
-    expected_state: String,
-    redirect_uri: String,
-    account_name: String,
-    cancelled: Arc<AtomicBool>,
+    listener: TcpListener,
+    ctx: CallbackContext,
+    cancel: CancellationToken,
+    timeout: Duration,
+    progress: &LoginProgress,
 ) -> Result<OAuthLoginResult> {
-    let timeout = Duration::from_secs(300); // 5 minute timeout
-    let start = std::time::Instant::now();
+    let deadline = tokio::time::sleep(timeout);
+    tokio::pin!(deadline);
 
     loop {
-        if cancelled.load(Ordering::Relaxed) {
-            anyhow::bail!("OAuth login cancelled");
-        }
-
-        if start.elapsed() > timeout {
-            anyhow::bail!("OAuth login timed out");
-        }
-
-        // Use recv_timeout to allow checking the timeout
-        let request = match server.recv_timeout(Duration::from_secs(1)) {
-            Ok(Some(req)) => req,
-            Ok(None) => continue,
-            Err(_) => continue,
+        let stream = tokio::select! {
+            _ = cancel.cancelled() => return Err(LoginAborted::Cancelled.into()),
+            _ = &mut deadline => return Err(LoginAborted::TimedOut.into()),
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    println!("[OAuth] Failed to accept callback connection: {e}");
+                    continue;
+                }
+            },
         };
 
-        let result = handle_oauth_request(
-            request,
-            &pkce,
-            &expected_state,
-            &redirect_uri,
-            &account_name,
-        )
-        .await;
+        let result = tokio::select! {
+            _ = cancel.cancelled() => return Err(LoginAborted::Cancelled.into()),
+            result = handle_oauth_connection(stream, &ctx, progress) => result,
+        };
 
         match result {
             HandleResult::Continue => continue,
-            HandleResult::Success(account) => {
-                server.unblock();
-                return Ok(OAuthLoginResult { account });
-            }
-            HandleResult::Error(e) => {
-                server.unblock();
-                return Err(e);
-            }
+            HandleResult::Success(account) => return Ok(OAuthLoginResult { account: *account }),
+            HandleResult::Error(e) => return Err(e),
         }
     }
 }
 
 enum HandleResult {
     Continue,
-    Success(StoredAccount),
+    Success(Box<StoredAccount>),
     Error(anyhow::Error),
 }
 
-async fn handle_oauth_request(
-    request: Request,
-    pkce: &PkceCodes,
-    expected_state: &str,
-    redirect_uri: &str,
-    account_name: &str,
+/// Read the request line of an HTTP request and return its target (path + query)
+async fn read_request_target(stream: &mut TcpStream) -> Option<String> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+
+    loop {
+        let read = tokio::time::timeout(REQUEST_READ_TIMEOUT, stream.read(&mut chunk)).await;
+        let n = read.ok()?.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if buf.windows(4).any(|w| w == b"\r\n\r\n") || buf.len() > MAX_REQUEST_HEAD_BYTES {
+            break;
+        }
+    }
+
+    let head = String::from_utf8_lossy(&buf);
+    let mut parts = head.lines().next()?.split_whitespace();
+    let _method = parts.next()?;
+    parts.next().map(String::from)
+}
+
+/// Write a complete HTTP response and close the connection
+async fn respond(stream: &mut TcpStream, status: u16, content_type: &str, body: &str) {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Internal Server Error",
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn respond_text(stream: &mut TcpStream, status: u16, body: &str) {
+    respond(stream, status, "text/plain; charset=utf-8", body).await;
+}
+
+async fn handle_oauth_connection(
+    mut stream: TcpStream,
+    ctx: &CallbackContext,
+    progress: &LoginProgress,
 ) -> HandleResult {
-    let url_str = request.url().to_string();
-    let parsed = match url::Url::parse(&format!("http://localhost{url_str}")) {
+    let Some(target) = read_request_target(&mut stream).await else {
+        respond_text(&mut stream, 400, "Bad Request").await;
+        return HandleResult::Continue;
+    };
+
+    let parsed = match url::Url::parse(&format!("http://localhost{target}")) {
         Ok(u) => u,
         Err(_) => {
-            let _ = request.respond(Response::from_string("Bad Request").with_status_code(400));
+            respond_text(&mut stream, 400, "Bad Request").await;
             return HandleResult::Continue;
         }
     };
@@ -312,6 +426,7 @@ async fn handle_oauth_request(
 
     if path == "/auth/callback" {
         println!("[OAuth] Received callback request");
+        progress(OAuthProgressStage::CallbackReceived, None);
         let params: std::collections::HashMap<String, String> =
             parsed.query_pairs().into_owned().collect();
 
@@ -327,17 +442,19 @@ async fn handle_oauth_request(
                 .map(|s| s.as_str())
                 .unwrap_or("Unknown error");
             println!("[OAuth] Error from provider: {error} - {error_desc}");
-            let _ = request.respond(
-                Response::from_string(format!("OAuth Error: {error} - {error_desc}"))
-                    .with_status_code(400),
-            );
+            respond_text(
+                &mut stream,
+                400,
+                &format!("OAuth Error: {error} - {error_desc}"),
+            )
+            .await;
             return HandleResult::Error(anyhow::anyhow!("OAuth error: {error} - {error_desc}"));
         }
 
         // Verify state
-        if params.get("state").map(String::as_str) != Some(expected_state) {
+        if params.get("state").map(String::as_str) != Some(ctx.expected_state.as_str()) {
             println!("[OAuth] State mismatch!");
-            let _ = request.respond(Response::from_string("State mismatch").with_status_code(400));
+            respond_text(&mut stream, 400, "State mismatch").await;
             return HandleResult::Error(anyhow::anyhow!("OAuth state mismatch"));
         }
 
@@ -348,36 +465,44 @@ async fn handle_oauth_request(
             Some(c) if !c.is_empty() => c.clone(),
             _ => {
                 println!("[OAuth] Missing authorization code");
-                let _ = request.respond(
-                    Response::from_string("Missing authorization code").with_status_code(400),
-                );
+                respond_text(&mut stream, 400, "Missing authorization code").await;
                 return HandleResult::Error(anyhow::anyhow!("Missing authorization code"));
             }
         };
 
         println!("[OAuth] Got authorization code, exchanging for tokens...");
+        progress(OAuthProgressStage::Exchanging, None);
 
         // Exchange code for tokens
-        match exchange_code_for_tokens(DEFAULT_ISSUER, CLIENT_ID, redirect_uri, pkce, &code).await {
+        match exchange_code_for_tokens(
+            &ctx.endpoints.issuer,
+            &ctx.endpoints.client_id,
+            &ctx.redirect_uri,
+            &ctx.pkce,
+            &code,
+        )
+        .await
+        {
             Ok(tokens) => {
                 println!("[OAuth] Token exchange successful!");
-                // Parse claims from ID token
-                let (email, plan_type, chatgpt_account_id) =
-                    parse_id_token_claims(&tokens.id_token);
-
-                // Create the account
-                let account = StoredAccount::new_chatgpt(
-                    account_name.to_string(),
-                    email,
-                    plan_type,
-                    tokens.id_token,
-                    tokens.access_token,
-                    tokens.refresh_token,
-                    chatgpt_account_id,
-                );
-
-                // Send success response
-                let success_html = r#"<!DOCTYPE html>
+                let account = account_from_tokens(ctx.account_name.clone(), tokens);
+                respond(&mut stream, 200, "text/html; charset=utf-8", SUCCESS_HTML).await;
+                return HandleResult::Success(Box::new(account));
+            }
+            Err(e) => {
+                println!("[OAuth] Token exchange failed: {e}");
+                respond_text(&mut stream, 500, &format!("Token exchange failed: {e}")).await;
+                return HandleResult::Error(e);
+            }
+        }
+    }
+
+    // Handle other paths
+    respond_text(&mut stream, 404, "Not Found").await;
+    HandleResult::Continue
+}
+
+const SUCCESS_HTML: &str = r#"<!DOCTYPE html>
 <html>
 <head>
     <title>Login Successful</title>
@@ -398,30 +523,6 @@ async fn handle_oauth_request(
 </body>
 </html>"#;
 
-                let response = Response::from_string(success_html).with_header(
-                    Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..])
-                        .unwrap(),
-                );
-                let _ = request.respond(response);
-
-                return HandleResult::Success(account);
-            }
-            Err(e) => {
-                println!("[OAuth] Token exchange failed: {e}");
-                let _ = request.respond(
-                    Response::from_string(format!("Token exchange failed: {e}"))
-                        .with_status_code(500),
-                );
-                return HandleResult::Error(e);
-            }
-        }
-    }
-
-    // Handle other paths
-    let _ = request.respond(Response::from_string("Not Found").with_status_code(404));
-    HandleResult::Continue
-}
-
 /// Wait for the OAuth login to complete
 pub async fn wait_for_oauth_login(
     rx: oneshot::Receiver<Result<OAuthLoginResult>>,
@@ -429,3 +530,367 @@ pub async fn wait_for_oauth_login(
     let result = rx.await.context("OAuth login was cancelled")??;
     Ok(result.account)
 }
+
+// ============================================================================
+// Headless login: paste the final redirect URL (or code) back into the app
+// ============================================================================
+
+/// A pending login that is completed by pasting the redirect URL manually.
+/// No local callback server is started, so this works on machines whose
+/// browser cannot reach `127.0.0.1`.
+#[derive(Debug, Clone)]
+pub struct ManualOAuthLogin {
+    pkce: PkceCodes,
+    state: String,
+    redirect_uri: String,
+    endpoints: EndpointConfig,
+    account_name: String,
+}
+
+/// Start a headless login flow. The browser is expected to fail to load the
+/// redirect page; the user copies the URL from the address bar instead.
+pub fn start_manual_oauth_login(
+    account_name: String,
+) -> Result<(OAuthLoginInfo, ManualOAuthLogin)> {
+    let endpoints = endpoint_config()?;
+    let pkce = generate_pkce();
+    let state = generate_state();
+    let redirect_uri = format!("http://localhost:{DEFAULT_PORT}/auth/callback");
+    let auth_url = build_authorize_url(
+        &endpoints.issuer,
+        &endpoints.client_id,
+        &redirect_uri,
+        &pkce,
+        &state,
+    );
+
+    println!("[OAuth] Starting headless login for account: {account_name}");
+
+    let login_info = OAuthLoginInfo {
+        session_id: String::new(),
+        auth_url,
+        callback_port: DEFAULT_PORT,
+    };
+
+    let pending = ManualOAuthLogin {
+        pkce,
+        state,
+        redirect_uri,
+        endpoints,
+        account_name,
+    };
+
+    Ok((login_info, pending))
+}
+
+/// Finish a headless login from the pasted redirect URL, query string or bare code
+pub async fn complete_manual_oauth_login(
+    login: &ManualOAuthLogin,
+    pasted: &str,
+) -> Result<StoredAccount> {
+    let code = parse_pasted_callback(pasted, &login.state)?;
+
+    println!("[OAuth] Got pasted authorization code, exchanging for tokens...");
+    let tokens = exchange_code_for_tokens(
+        &login.endpoints.issuer,
+        &login.endpoints.client_id,
+        &login.redirect_uri,
+        &login.pkce,
+        &code,
+    )
+    .await?;
+    println!("[OAuth] Token exchange successful!");
+
+    Ok(account_from_tokens(login.account_name.clone(), tokens))
+}
+
+/// Extract the authorization code from whatever the user pasted.
+/// Accepts a full callback URL or a bare query string (both must carry the
+/// expected `state`), or the code itself.
+fn parse_pasted_callback(pasted: &str, expected_state: &str) -> Result<String> {
+    let pasted = pasted.trim();
+    if pasted.is_empty() {
+        anyhow::bail!("Nothing was pasted");
+    }
+
+    let query = if let Ok(parsed) = url::Url::parse(pasted) {
+        parsed.query().unwrap_or_default().to_string()
+    } else if pasted.contains('=') {
+        pasted.trim_start_matches('?').to_string()
+    } else {
+        return Ok(pasted.to_string());
+    };
+
+    let params: std::collections::HashMap<String, String> =
+        url::form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+
+    if let Some(error) = params.get("error") {
+        let error_desc = params
+            .get("error_description")
+            .map(|s| s.as_str())
+            .unwrap_or("Unknown error");
+        anyhow::bail!("OAuth error: {error} - {error_desc}");
+    }
+
+    // A URL or query string must carry the state we sent (CSRF protection)
+    match params.get("state") {
+        Some(state) if state == expected_state => {}
+        Some(_) => anyhow::bail!("OAuth state mismatch"),
+        None => anyhow::bail!("Pasted URL is missing the `state` parameter"),
+    }
+
+    match params.get("code") {
+        Some(code) if !code.is_empty() => Ok(code.clone()),
+        _ => anyhow::bail!("Pasted URL does not contain an authorization code"),
+    }
+}
+
+// ============================================================================
+// Device authorization flow (RFC 8628)
+// ============================================================================
+
+/// Subset of the issuer's OpenID configuration used for device login and revocation
+#[derive(Debug, Clone, serde::Deserialize)]
+pub(crate) struct IssuerMetadata {
+    #[serde(default)]
+    pub device_authorization_endpoint: Option<String>,
+    #[serde(default)]
+    pub token_endpoint: Option<String>,
+    #[serde(default)]
+    pub revocation_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct DeviceAuthorizationResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    #[serde(default)]
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    #[serde(default)]
+    interval: Option<u64>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct DeviceTokenError {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+pub(crate) async fn fetch_issuer_metadata(
+    client: &reqwest::Client,
+    issuer: &str,
+) -> Result<IssuerMetadata> {
+    let resp = client
+        .get(format!("{issuer}/.well-known/openid-configuration"))
+        .send()
+        .await
+        .context("Failed to fetch issuer configuration")?;
+
+    if !resp.status().is_success() {
+        anyhow::bail!("Failed to fetch issuer configuration: {}", resp.status());
+    }
+
+    resp.json()
+        .await
+        .context("Failed to parse issuer configuration")
+}
+
+/// Start the device authorization flow, if the issuer supports it.
+/// The returned receiver resolves once the user approved the code in a browser
+/// on any device.
+pub async fn start_device_oauth_login(
+    account_name: String,
+    progress: LoginProgress,
+) -> Result<(
+    DeviceLoginInfo,
+    oneshot::Receiver<Result<OAuthLoginResult>>,
+    CancellationToken,
+)> {
+    let endpoints = endpoint_config()?;
+    let client = http_client()?;
+    let metadata = fetch_issuer_metadata(&client, &endpoints.issuer).await?;
+
+    let device_endpoint = metadata
+        .device_authorization_endpoint
+        .context("The OAuth issuer does not support device authorization")?;
+    let token_endpoint = metadata
+        .token_endpoint
+        .unwrap_or_else(|| format!("{}/oauth/token", endpoints.issuer));
+
+    println!("[OAuth] Starting device login for account: {account_name}");
+
+    let body = format!(
+        "client_id={}&scope={}",
+        urlencoding::encode(&endpoints.client_id),
+        urlencoding::encode("openid profile email offline_access"),
+    );
+
+    let resp = client
+        .post(&device_endpoint)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await
+        .context("Failed to send device authorization request")?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Device authorization failed: {status} - {body}");
+    }
+
+    let device: DeviceAuthorizationResponse = resp
+        .json()
+        .await
+        .context("Failed to parse device authorization response")?;
+
+    let interval = device.interval.unwrap_or(DEFAULT_DEVICE_POLL_INTERVAL_SECS);
+
+    let login_info = DeviceLoginInfo {
+        session_id: String::new(),
+        verification_uri: device.verification_uri.clone(),
+        verification_uri_complete: device.verification_uri_complete.clone(),
+        user_code: device.user_code.clone(),
+        expires_in: device.expires_in,
+        interval,
+    };
+
+    progress(OAuthProgressStage::WaitingForApproval, None);
+
+    let (tx, rx) = oneshot::channel();
+    let cancel = CancellationToken::new();
+    let cancel_clone = cancel.clone();
+
+    tokio::spawn(async move {
+        let result = poll_device_token(
+            client,
+            token_endpoint,
+            endpoints.client_id,
+            device,
+            interval,
+            account_name,
+            cancel_clone,
+        )
+        .await;
+        report_outcome(&progress, &result);
+        let _ = tx.send(result);
+    });
+
+    Ok((login_info, rx, cancel))
+}
+
+/// Poll the token endpoint until the user approves or denies the device code
+async fn poll_device_token(
+    client: reqwest::Client,
+    token_endpoint: String,
+    client_id: String,
+    device: DeviceAuthorizationResponse,
+    mut interval: u64,
+    account_name: String,
+    cancel: CancellationToken,
+) -> Result<OAuthLoginResult> {
+    let deadline = std::time::Instant::now() + Duration::from_secs(device.expires_in);
+    let body = format!(
+        "grant_type={}&device_code={}&client_id={}",
+        urlencoding::encode(DEVICE_CODE_GRANT_TYPE),
+        urlencoding::encode(&device.device_code),
+        urlencoding::encode(&client_id),
+    );
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return Err(LoginAborted::Cancelled.into()),
+            _ = tokio::time::sleep(Duration::from_secs(interval)) => {}
+        }
+
+        if std::time::Instant::now() > deadline {
+            anyhow::bail!("Device code expired before it was approved");
+        }
+
+        let resp = match client
+            .post(&token_endpoint)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body.clone())
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(err) => {
+                println!("[OAuth] Device token poll failed, retrying: {err}");
+                continue;
+            }
+        };
+
+        if resp.status().is_success() {
+            let tokens: TokenResponse = resp
+                .json()
+                .await
+                .context("Failed to parse token response")?;
+            println!("[OAuth] Device login approved");
+            return Ok(OAuthLoginResult {
+                account: account_from_tokens(account_name, tokens),
+            });
+        }
+
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        let error = match serde_json::from_str::<DeviceTokenError>(&body) {
+            Ok(error) => error,
+            Err(_) => anyhow::bail!("Device token request failed: {status} - {body}"),
+        };
+
+        match error.error.as_str() {
+            "authorization_pending" => continue,
+            "slow_down" => interval += 5,
+            "access_denied" => anyhow::bail!("Device login was denied"),
+            "expired_token" => anyhow::bail!("Device code expired before it was approved"),
+            other => anyhow::bail!(
+                "Device token request failed: {other} - {}",
+                error.error_description.unwrap_or_default()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pasted_callback_requires_matching_state() {
+        let url = "http://localhost:1455/auth/callback?code=abc&state=xyz";
+        assert_eq!(parse_pasted_callback(url, "xyz").unwrap(), "abc");
+        assert_eq!(
+            parse_pasted_callback("?code=abc&state=xyz", "xyz").unwrap(),
+            "abc"
+        );
+
+        assert!(parse_pasted_callback(url, "other").is_err());
+        assert!(
+            parse_pasted_callback("http://localhost:1455/auth/callback?code=abc", "xyz").is_err()
+        );
+        assert!(parse_pasted_callback("code=abc", "xyz").is_err());
+    }
+
+    #[test]
+    fn pasted_code_is_accepted_as_is() {
+        assert_eq!(
+            parse_pasted_callback("  abc123\n", "xyz").unwrap(),
+            "abc123"
+        );
+        assert!(parse_pasted_callback("   ", "xyz").is_err());
+    }
+
+    #[test]
+    fn pasted_error_is_reported() {
+        let error =
+            parse_pasted_callback("error=access_denied&error_description=No&state=xyz", "xyz")
+                .unwrap_err();
+        assert_eq!(error.to_string(), "OAuth error: access_denied - No");
+    }
+}
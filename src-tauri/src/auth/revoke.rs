@@ -0,0 +1,180 @@
+//! OAuth token revocation (RFC 7009)
+
+use anyhow::{Context, Result};
+
+use super::oauth_server::fetch_issuer_metadata;
+use crate::api::http_client;
+use crate::settings::endpoint_config;
+use crate::types::{AuthData, RevocationResult, StoredAccount};
+
+/// Revoke an account's refresh token at the issuer and report the outcome.
+/// Never fails: errors are reported in the result so callers can continue
+/// removing the account locally.
+pub async fn revoke_account_tokens(account: &StoredAccount) -> RevocationResult {
+    let refresh_token = match &account.auth_data {
+        AuthData::ChatGPT { refresh_token, .. } => refresh_token,
+        AuthData::ApiKey { .. } => {
+            return RevocationResult {
+                requested: true,
+                revoked: false,
+                error: Some(
+                    "API keys cannot be revoked from here; delete the key in the OpenAI dashboard"
+                        .to_string(),
+                ),
+            };
+        }
+    };
+
+    if refresh_token.is_empty() {
+        return RevocationResult {
+            requested: true,
+            revoked: false,
+            error: Some("Account has no refresh token".to_string()),
+        };
+    }
+
+    match revoke_refresh_token(refresh_token).await {
+        Ok(()) => {
+            println!("[Auth] Revoked refresh token for account {}", account.name);
+            RevocationResult {
+                requested: true,
+                revoked: true,
+                error: None,
+            }
+        }
+        Err(e) => {
+            println!(
+                "[Auth] Failed to revoke refresh token for account {}: {e:#}",
+                account.name
+            );
+            RevocationResult {
+                requested: true,
+                revoked: false,
+                error: Some(format!("{e:#}")),
+            }
+        }
+    }
+}
+
+/// Revoke a refresh token at the issuer's revocation endpoint
+pub async fn revoke_refresh_token(refresh_token: &str) -> Result<()> {
+    let endpoints = endpoint_config()?;
+    let client = http_client()?;
+    revoke_at_issuer(
+        &client,
+        &endpoints.issuer,
+        &endpoints.client_id,
+        refresh_token,
+    )
+    .await
+}
+
+async fn revoke_at_issuer(
+    client: &reqwest::Client,
+    issuer: &str,
+    client_id: &str,
+    refresh_token: &str,
+) -> Result<()> {
+    // Prefer the advertised endpoint; fall back to the conventional path
+    let revocation_endpoint = match fetch_issuer_metadata(client, issuer).await {
+        Ok(metadata) => metadata.revocation_endpoint,
+        Err(e) => {
+            println!("[Auth] Could not discover revocation endpoint: {e}");
+            None
+        }
+    }
+    .unwrap_or_else(|| format!("{issuer}/oauth/revoke"));
+
+    let body = format!(
+        "token={}&token_type_hint=refresh_token&client_id={}",
+        urlencoding::encode(refresh_token),
+        urlencoding::encode(client_id),
+    );
+
+    let response = client
+        .post(&revocation_endpoint)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await
+        .context("Failed to send token revocation request")?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Token revocation failed: {status} - {body}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{StandIn, StandInResponse};
+
+    #[tokio::test]
+    async fn revokes_at_advertised_endpoint() {
+        let revoker = StandIn::start(vec![StandInResponse::new(200, "")]).await;
+        let metadata = format!(
+            r#"{{"revocation_endpoint":"{}/custom/revoke"}}"#,
+            revoker.url
+        );
+        let issuer = StandIn::start(vec![StandInResponse::new(200, metadata)]).await;
+
+        revoke_at_issuer(&reqwest::Client::new(), &issuer.url, "app_client", "rt/a+b")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            issuer.requests()[0].path,
+            "/.well-known/openid-configuration"
+        );
+        let request = &revoker.requests()[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/custom/revoke");
+        assert_eq!(
+            request.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            request.body,
+            "token=rt%2Fa%2Bb&token_type_hint=refresh_token&client_id=app_client"
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_conventional_path_without_metadata() {
+        let issuer = StandIn::start(vec![
+            StandInResponse::new(404, "not found"),
+            StandInResponse::new(200, ""),
+        ])
+        .await;
+
+        revoke_at_issuer(&reqwest::Client::new(), &issuer.url, "app_client", "rt")
+            .await
+            .unwrap();
+
+        let requests = issuer.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].path, "/oauth/revoke");
+    }
+
+    #[tokio::test]
+    async fn reports_rejected_revocation() {
+        let issuer = StandIn::start(vec![
+            StandInResponse::new(200, "{}"),
+            StandInResponse::new(400, r#"{"error":"invalid_client"}"#),
+        ])
+        .await;
+
+        let error = revoke_at_issuer(&reqwest::Client::new(), &issuer.url, "bad", "rt")
+            .await
+            .unwrap_err();
+
+        let message = error.to_string();
+        assert!(message.contains("400"), "{message}");
+        assert!(message.contains("invalid_client"), "{message}");
+    }
+}
@@ -4,8 +4,12 @@ use std::fs;
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
+use chrono::Utc;
 
-use crate::types::{AccountsStore, AuthData, LastNotifications, StoredAccount};
+use crate::types::{
+    AccountWorkspace, AccountsStore, AuthData, LastNotifications, NotificationOverrides,
+    NotificationSettings, StoredAccount, ACCOUNTS_STORE_VERSION,
+};
 
 /// Get the path to the codex-switcher config directory
 pub fn get_config_dir() -> Result<PathBuf> {
@@ -29,12 +33,37 @@ pub fn load_accounts() -> Result<AccountsStore> {
     let content = fs::read_to_string(&path)
         .with_context(|| format!("Failed to read accounts file: {}", path.display()))?;
 
-    let store: AccountsStore = serde_json::from_str(&content)
+    let mut store: AccountsStore = serde_json::from_str(&content)
         .with_context(|| format!("Failed to parse accounts file: {}", path.display()))?;
 
+    if migrate_accounts_store(&mut store) {
+        println!("[Storage] Migrated accounts file to version {ACCOUNTS_STORE_VERSION}");
+        save_accounts(&store)?;
+    }
+
     Ok(store)
 }
 
+/// Bring a store written by an older version up to date.
+/// Returns whether anything changed.
+pub fn migrate_accounts_store(store: &mut AccountsStore) -> bool {
+    if store.version >= ACCOUNTS_STORE_VERSION {
+        return false;
+    }
+
+    // Before version 3 every account stored complete notification settings.
+    // Keep only the fields that differ from the built-in defaults so the
+    // global defaults apply to everything else.
+    let builtin = NotificationSettings::default();
+    for account in &mut store.accounts {
+        let settings = account.notification_settings.resolve(&builtin);
+        account.notification_settings = NotificationOverrides::diff(&settings, &builtin);
+    }
+
+    store.version = ACCOUNTS_STORE_VERSION;
+    true
+}
+
 /// Save the accounts store to disk
 pub fn save_accounts(store: &AccountsStore) -> Result<()> {
     let path = get_accounts_file()?;
@@ -103,6 +132,38 @@ pub fn remove_account(account_id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Clear the active account ID
+pub fn clear_active_account() -> Result<()> {
+    let mut store = load_accounts()?;
+    store.active_account_id = None;
+    save_accounts(&store)
+}
+
+/// Drop a ChatGPT account's tokens after they were revoked, leaving it signed out
+pub fn clear_account_tokens(account_id: &str) -> Result<()> {
+    let mut store = load_accounts()?;
+
+    let account = store
+        .accounts
+        .iter_mut()
+        .find(|a| a.id == account_id)
+        .context("Account not found")?;
+
+    if let AuthData::ChatGPT {
+        id_token,
+        access_token,
+        refresh_token,
+        ..
+    } = &mut account.auth_data
+    {
+        id_token.clear();
+        access_token.clear();
+        refresh_token.clear();
+    }
+
+    save_accounts(&store)
+}
+
 /// Update the active account ID
 pub fn set_active_account(account_id: &str) -> Result<()> {
     let mut store = load_accounts()?;
@@ -206,24 +267,25 @@ pub fn update_account_chatgpt_tokens(
         .find(|a| a.id == account_id)
         .context("Account not found")?;
 
-    match &mut account.auth_data {
+    let previous_refresh_token = match &mut account.auth_data {
         AuthData::ChatGPT {
             id_token: stored_id_token,
             access_token: stored_access_token,
             refresh_token: stored_refresh_token,
             account_id: stored_account_id,
         } => {
-            *stored_id_token = id_token;
-            *stored_access_token = access_token;
-            *stored_refresh_token = refresh_token;
+            *stored_id_token = id_token.clone();
+            *stored_access_token = access_token.clone();
+            let previous = std::mem::replace(stored_refresh_token, refresh_token.clone());
             if let Some(new_account_id) = chatgpt_account_id {
                 *stored_account_id = Some(new_account_id);
             }
+            previous
         }
         AuthData::ApiKey { .. } => {
             anyhow::bail!("Cannot update OAuth tokens for an API key account");
         }
-    }
+    };
 
     if let Some(new_email) = email {
         account.email = Some(new_email);
@@ -233,11 +295,131 @@ pub fn update_account_chatgpt_tokens(
         account.plan_type = Some(new_plan_type);
     }
 
+    let now = Utc::now();
+    let rotated = previous_refresh_token != refresh_token;
+    account.last_refresh_at = Some(now);
+    if rotated {
+        account.refresh_token_issued_at = Some(now);
+    }
+
     let updated = account.clone();
+
+    // Workspace accounts created from the same login share its refresh token.
+    // Refresh tokens rotate, so hand the new tokens to every sibling as well.
+    for sibling in store.accounts.iter_mut().filter(|a| a.id != account_id) {
+        if let AuthData::ChatGPT {
+            id_token: sibling_id_token,
+            access_token: sibling_access_token,
+            refresh_token: sibling_refresh_token,
+            ..
+        } = &mut sibling.auth_data
+        {
+            if previous_refresh_token.is_empty() || *sibling_refresh_token != previous_refresh_token
+            {
+                continue;
+            }
+            *sibling_id_token = id_token.clone();
+            *sibling_access_token = access_token.clone();
+            *sibling_refresh_token = refresh_token.clone();
+            sibling.last_refresh_at = Some(now);
+            if rotated {
+                sibling.refresh_token_issued_at = Some(now);
+            }
+        }
+    }
+
     save_accounts(&store)?;
     Ok(updated)
 }
 
+/// Create another account from an existing ChatGPT login, pinned to one of its workspaces.
+/// The new account shares the login's tokens but sends the workspace as its account ID.
+pub fn add_workspace_account(
+    account_id: &str,
+    workspace_id: &str,
+    name: String,
+) -> Result<StoredAccount> {
+    let store = load_accounts()?;
+    let source = store
+        .accounts
+        .iter()
+        .find(|a| a.id == account_id)
+        .context("Account not found")?;
+
+    let AuthData::ChatGPT {
+        id_token,
+        access_token,
+        refresh_token,
+        ..
+    } = &source.auth_data
+    else {
+        anyhow::bail!("Workspaces are only available for ChatGPT accounts");
+    };
+
+    let workspaces = source.workspaces.clone();
+    if !workspaces.iter().any(|w| w.id == workspace_id) {
+        anyhow::bail!("Workspace '{workspace_id}' is not available for this account");
+    }
+
+    let duplicate = store.accounts.iter().any(|a| match &a.auth_data {
+        AuthData::ChatGPT {
+            refresh_token: other_refresh_token,
+            account_id: other_account_id,
+            ..
+        } => {
+            other_refresh_token == refresh_token
+                && other_account_id.as_deref() == Some(workspace_id)
+        }
+        AuthData::ApiKey { .. } => false,
+    });
+    if duplicate {
+        anyhow::bail!("An account for workspace '{workspace_id}' already exists");
+    }
+
+    let mut account = StoredAccount::new_chatgpt(
+        name,
+        source.email.clone(),
+        source.plan_type.clone(),
+        id_token.clone(),
+        access_token.clone(),
+        refresh_token.clone(),
+        Some(workspace_id.to_string()),
+    );
+    account.workspaces = workspaces;
+    account.workspace_id = Some(workspace_id.to_string());
+    account.last_refresh_at = source.last_refresh_at;
+    account.refresh_token_issued_at = source.refresh_token_issued_at;
+
+    add_account(account)
+}
+
+/// Store the workspaces of a login on the account and on every account sharing its refresh token
+pub fn set_account_workspaces(account_id: &str, workspaces: Vec<AccountWorkspace>) -> Result<()> {
+    let mut store = load_accounts()?;
+    let refresh_token = store
+        .accounts
+        .iter()
+        .find(|a| a.id == account_id)
+        .context("Account not found")?
+        .auth_data
+        .clone();
+    let AuthData::ChatGPT { refresh_token, .. } = refresh_token else {
+        anyhow::bail!("Workspaces are only available for ChatGPT accounts");
+    };
+
+    for account in &mut store.accounts {
+        let shares_login = matches!(
+            &account.auth_data,
+            AuthData::ChatGPT { refresh_token: other, .. } if *other == refresh_token
+        );
+        if account.id == account_id || shares_login {
+            account.workspaces = workspaces.clone();
+        }
+    }
+
+    save_accounts(&store)
+}
+
 /// Update last notification timestamps for an account
 pub fn update_last_notifications(
     account_id: &str,
@@ -252,3 +434,63 @@ pub fn update_last_notifications(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_store(settings: serde_json::Value) -> AccountsStore {
+        let account = StoredAccount::new_api_key("Work".to_string(), "sk-test".to_string());
+        let mut account = serde_json::to_value(account).unwrap();
+        account["notification_settings"] = settings;
+        serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "accounts": [account],
+            "active_account_id": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn migration_drops_legacy_fields_equal_to_defaults() {
+        let mut store = legacy_store(serde_json::json!({
+            "enabled": false,
+            "primary_threshold": 80,
+            "secondary_threshold": 80,
+            "credits_threshold": 20,
+            "min_interval_minutes": 60,
+        }));
+
+        assert!(migrate_accounts_store(&mut store));
+        assert_eq!(store.version, ACCOUNTS_STORE_VERSION);
+        assert_eq!(
+            store.accounts[0].notification_settings,
+            NotificationOverrides::default()
+        );
+    }
+
+    #[test]
+    fn migration_keeps_customized_legacy_fields() {
+        let mut store = legacy_store(serde_json::json!({
+            "enabled": true,
+            "primary_threshold": 90,
+            "secondary_threshold": null,
+            "credits_threshold": 20,
+            "min_interval_minutes": 60,
+        }));
+
+        migrate_accounts_store(&mut store);
+        let overrides = &store.accounts[0].notification_settings;
+        assert_eq!(overrides.enabled, Some(true));
+        assert_eq!(overrides.primary_threshold, Some(Some(90)));
+        assert_eq!(overrides.secondary_threshold, Some(None));
+        assert_eq!(overrides.credits_threshold, None);
+        assert_eq!(overrides.min_interval_minutes, None);
+    }
+
+    #[test]
+    fn migration_leaves_current_stores_alone() {
+        let mut store = AccountsStore::default();
+        assert!(!migrate_accounts_store(&mut store));
+    }
+}
@@ -6,6 +6,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use chrono::Utc;
 
+use super::IdTokenClaims;
 use crate::types::{AuthData, AuthDotJson, StoredAccount, TokenData};
 
 /// Get the official Codex home directory
@@ -26,6 +27,10 @@ pub fn get_codex_auth_file() -> Result<PathBuf> {
 
 /// Switch to a specific account by writing its credentials to ~/.codex/auth.json
 pub fn switch_to_account(account: &StoredAccount) -> Result<()> {
+    if account.is_signed_out() {
+        anyhow::bail!("Account {} is signed out; log in again first", account.name);
+    }
+
     let codex_home = get_codex_home()?;
 
     // Ensure the codex home directory exists
@@ -52,6 +57,18 @@ pub fn switch_to_account(account: &StoredAccount) -> Result<()> {
     Ok(())
 }
 
+/// Remove ~/.codex/auth.json so Codex is logged out
+pub fn clear_codex_auth() -> Result<()> {
+    let auth_path = get_codex_auth_file()?;
+
+    if auth_path.exists() {
+        fs::remove_file(&auth_path)
+            .with_context(|| format!("Failed to remove auth.json: {}", auth_path.display()))?;
+    }
+
+    Ok(())
+}
+
 /// Create an AuthDotJson structure from a StoredAccount
 fn create_auth_json(account: &StoredAccount) -> Result<AuthDotJson> {
     match &account.auth_data {
@@ -90,54 +107,27 @@ pub fn import_from_auth_json(path: &str, account_name: String) -> Result<StoredA
     if let Some(api_key) = auth.openai_api_key {
         Ok(StoredAccount::new_api_key(account_name, api_key))
     } else if let Some(tokens) = auth.tokens {
-        // Try to extract email and plan from id_token
-        let (email, plan_type) = parse_id_token_claims(&tokens.id_token);
+        // Try to extract email, plan and workspaces from id_token
+        let claims = IdTokenClaims::parse(&tokens.id_token).unwrap_or_default();
+        let workspaces = claims.default_workspace().into_iter().collect();
 
-        Ok(StoredAccount::new_chatgpt(
+        let mut account = StoredAccount::new_chatgpt(
             account_name,
-            email,
-            plan_type,
+            claims.email,
+            claims.plan_type,
             tokens.id_token,
             tokens.access_token,
             tokens.refresh_token,
             tokens.account_id,
-        ))
+        );
+        account.workspaces = workspaces;
+        account.last_refresh_at = auth.last_refresh;
+        Ok(account)
     } else {
         anyhow::bail!("auth.json contains neither API key nor tokens");
     }
 }
 
-/// Parse claims from a JWT ID token (without validation)
-fn parse_id_token_claims(id_token: &str) -> (Option<String>, Option<String>) {
-    let parts: Vec<&str> = id_token.split('.').collect();
-    if parts.len() != 3 {
-        return (None, None);
-    }
-
-    // Decode the payload (second part)
-    let payload =
-        match base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, parts[1]) {
-            Ok(bytes) => bytes,
-            Err(_) => return (None, None),
-        };
-
-    let json: serde_json::Value = match serde_json::from_slice(&payload) {
-        Ok(v) => v,
-        Err(_) => return (None, None),
-    };
-
-    let email = json.get("email").and_then(|v| v.as_str()).map(String::from);
-
-    // Look for plan type in the OpenAI auth claims
-    let plan_type = json
-        .get("https://api.openai.com/auth")
-        .and_then(|auth| auth.get("chatgpt_plan_type"))
-        .and_then(|v| v.as_str())
-        .map(String::from);
-
-    (email, plan_type)
-}
-
 /// Read the current auth.json file if it exists
 pub fn read_current_auth() -> Result<Option<AuthDotJson>> {
     let path = get_codex_auth_file()?;
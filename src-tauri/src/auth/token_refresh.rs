@@ -1,15 +1,21 @@
 //! ChatGPT OAuth token refresh helpers
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use anyhow::{Context, Result};
-use base64::Engine;
 use chrono::Utc;
+use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 
-use super::{load_accounts, switch_to_account, update_account_chatgpt_tokens};
+use super::{
+    get_account, get_active_account, load_accounts, switch_to_account,
+    update_account_chatgpt_tokens, AccessTokenClaims, IdTokenClaims,
+};
+use crate::api::http_client;
+use crate::settings::endpoint_config;
 use crate::types::{AuthData, StoredAccount};
 
-const DEFAULT_ISSUER: &str = "https://auth.openai.com";
-const CLIENT_ID: &str = "app_EMoamEEZ73f0CkXaXp7hrann";
 const EXPIRY_SKEW_SECONDS: i64 = 60;
 
 #[derive(Debug, serde::Deserialize)]
@@ -40,8 +46,45 @@ pub async fn ensure_chatgpt_tokens_fresh(account: &StoredAccount) -> Result<Stor
     }
 }
 
+/// The issuer answered a refresh request with an error status
+/// (e.g. the refresh token was revoked or already rotated)
+#[derive(Debug, thiserror::Error)]
+#[error("Token refresh failed: {status} - {body}")]
+pub struct TokenRefreshRejected {
+    pub status: u16,
+    pub body: String,
+}
+
+/// One lock per refresh token. Usage is fetched concurrently, and refresh tokens
+/// rotate, so two tasks refreshing the same login (or workspace siblings sharing
+/// it) at once would invalidate each other. Different logins refresh in parallel.
+static REFRESH_LOCKS: std::sync::Mutex<Option<HashMap<String, Arc<Mutex<()>>>>> =
+    std::sync::Mutex::new(None);
+
+/// The lock serialising refreshes of one refresh token
+fn refresh_lock(refresh_token: &str) -> Arc<Mutex<()>> {
+    let mut locks = REFRESH_LOCKS.lock().unwrap();
+    let locks = locks.get_or_insert_with(HashMap::new);
+    // Drop locks nobody holds or waits for; rotated-out tokens are never used again
+    locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+    locks.entry(refresh_token.to_string()).or_default().clone()
+}
+
 /// Force-refresh ChatGPT OAuth tokens for an account.
 pub async fn refresh_chatgpt_tokens(account: &StoredAccount) -> Result<StoredAccount> {
+    let Some(refresh_token) = refresh_token_of(account) else {
+        return Ok(account.clone());
+    };
+    let lock = refresh_lock(refresh_token);
+    let _guard = lock.lock().await;
+
+    // Another task may have refreshed this login (or a workspace sibling) while we waited
+    if let Some(stored) = get_account(&account.id)? {
+        if refresh_token_of(&stored) != refresh_token_of(account) {
+            return Ok(stored);
+        }
+    }
+
     let (current_id_token, current_refresh_token, current_account_id) = match &account.auth_data {
         AuthData::ApiKey { .. } => return Ok(account.clone()),
         AuthData::ChatGPT {
@@ -62,10 +105,21 @@ pub async fn refresh_chatgpt_tokens(account: &StoredAccount) -> Result<StoredAcc
         .refresh_token
         .unwrap_or_else(|| current_refresh_token.clone());
 
-    let (email, plan_type, parsed_account_id) = parse_id_token_claims(&next_id_token);
-    let next_account_id = parsed_account_id.or(current_account_id);
+    let claims = IdTokenClaims::parse(&next_id_token).unwrap_or_default();
+    // A workspace-pinned account keeps its workspace; the ID token only names the default one.
+    let next_account_id = account
+        .workspace_id
+        .clone()
+        .or(claims.chatgpt_account_id)
+        .or(current_account_id);
 
-    let is_active = load_accounts()?.active_account_id.as_deref() == Some(account.id.as_str());
+    // The active account may be this one or a workspace sibling sharing the login
+    let store = load_accounts()?;
+    let active_shares_login = store
+        .active_account_id
+        .as_deref()
+        .and_then(|id| store.accounts.iter().find(|a| a.id == id))
+        .is_some_and(|active| refresh_token_of(active) == Some(current_refresh_token.as_str()));
 
     let updated = update_account_chatgpt_tokens(
         &account.id,
@@ -73,13 +127,13 @@ pub async fn refresh_chatgpt_tokens(account: &StoredAccount) -> Result<StoredAcc
         refreshed.access_token,
         next_refresh_token,
         next_account_id,
-        email,
-        plan_type,
+        claims.email,
+        claims.plan_type,
     )?;
 
-    // Keep ~/.codex/auth.json in sync when this is the active account.
-    if is_active {
-        if let Err(err) = switch_to_account(&updated) {
+    // Keep ~/.codex/auth.json in sync: it still holds the rotated-out refresh token
+    if active_shares_login {
+        if let Err(err) = sync_active_auth_json() {
             println!("[Auth] Failed to sync active auth.json after token refresh: {err}");
         }
     }
@@ -102,75 +156,53 @@ pub async fn create_chatgpt_account_from_refresh_token(
         .id_token
         .context("Refresh response did not include id_token")?;
     let next_refresh_token = refreshed.refresh_token.unwrap_or(refresh_token);
-    let (email, plan_type, account_id) = parse_id_token_claims(&id_token);
+    let claims = IdTokenClaims::parse(&id_token).unwrap_or_default();
+    let workspaces = claims.default_workspace().into_iter().collect();
 
-    Ok(StoredAccount::new_chatgpt(
+    let mut account = StoredAccount::new_chatgpt(
         account_name,
-        email,
-        plan_type,
+        claims.email,
+        claims.plan_type,
         id_token,
         refreshed.access_token,
         next_refresh_token,
-        account_id,
-    ))
+        claims.chatgpt_account_id,
+    );
+    account.workspaces = workspaces;
+    account.last_refresh_at = Some(Utc::now());
+    account.refresh_token_issued_at = account.last_refresh_at;
+    Ok(account)
 }
 
-fn token_expired_or_near_expiry(access_token: &str) -> bool {
-    match parse_jwt_exp(access_token) {
-        Some(expiry) => expiry <= Utc::now().timestamp() + EXPIRY_SKEW_SECONDS,
-        None => false,
+/// Rewrite auth.json from the stored active account
+fn sync_active_auth_json() -> Result<()> {
+    if let Some(active) = get_active_account()? {
+        switch_to_account(&active)?;
     }
+    Ok(())
 }
 
-fn parse_jwt_exp(token: &str) -> Option<i64> {
-    let parts: Vec<&str> = token.split('.').collect();
-    if parts.len() != 3 {
-        return None;
+fn refresh_token_of(account: &StoredAccount) -> Option<&str> {
+    match &account.auth_data {
+        AuthData::ChatGPT { refresh_token, .. } => Some(refresh_token.as_str()),
+        AuthData::ApiKey { .. } => None,
     }
-
-    let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(parts[1])
-        .ok()?;
-    let json: serde_json::Value = serde_json::from_slice(&payload).ok()?;
-    json.get("exp").and_then(|v| v.as_i64())
 }
 
-fn parse_id_token_claims(id_token: &str) -> (Option<String>, Option<String>, Option<String>) {
-    let parts: Vec<&str> = id_token.split('.').collect();
-    if parts.len() != 3 {
-        return (None, None, None);
+fn token_expired_or_near_expiry(access_token: &str) -> bool {
+    match AccessTokenClaims::parse(access_token).and_then(|c| c.expires_at) {
+        Some(expiry) => expiry <= Utc::now() + chrono::Duration::seconds(EXPIRY_SKEW_SECONDS),
+        None => false,
     }
-
-    let payload = match base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(parts[1]) {
-        Ok(bytes) => bytes,
-        Err(_) => return (None, None, None),
-    };
-
-    let json: serde_json::Value = match serde_json::from_slice(&payload) {
-        Ok(v) => v,
-        Err(_) => return (None, None, None),
-    };
-
-    let email = json.get("email").and_then(|v| v.as_str()).map(String::from);
-    let auth_claims = json.get("https://api.openai.com/auth");
-    let plan_type = auth_claims
-        .and_then(|auth| auth.get("chatgpt_plan_type"))
-        .and_then(|v| v.as_str())
-        .map(String::from);
-    let account_id = auth_claims
-        .and_then(|auth| auth.get("chatgpt_account_id"))
-        .and_then(|v| v.as_str())
-        .map(String::from);
-
-    (email, plan_type, account_id)
 }
 
 async fn refresh_tokens_with_refresh_token(refresh_token: &str) -> Result<RefreshTokenResponse> {
-    let client = reqwest::Client::new();
+    let endpoints = endpoint_config()?;
+    let client = http_client()?;
     let body = format!(
         "grant_type=refresh_token&refresh_token={}&client_id={}",
         urlencoding::encode(refresh_token),
-        urlencoding::encode(CLIENT_ID),
+        urlencoding::encode(&endpoints.client_id),
     );
 
     let mut last_send_error = None;
@@ -178,7 +210,7 @@ async fn refresh_tokens_with_refresh_token(refresh_token: &str) -> Result<Refres
 
     for attempt in 1..=3u8 {
         match client
-            .post(format!("{DEFAULT_ISSUER}/oauth/token"))
+            .post(format!("{}/oauth/token", endpoints.issuer))
             .header("Content-Type", "application/x-www-form-urlencoded")
             .body(body.clone())
             .send()
@@ -206,9 +238,9 @@ async fn refresh_tokens_with_refresh_token(refresh_token: &str) -> Result<Refres
     };
 
     if !response.status().is_success() {
-        let status = response.status();
+        let status = response.status().as_u16();
         let body = response.text().await.unwrap_or_default();
-        anyhow::bail!("Token refresh failed: {status} - {body}");
+        return Err(TokenRefreshRejected { status, body }.into());
     }
 
     response
@@ -216,3 +248,39 @@ async fn refresh_tokens_with_refresh_token(refresh_token: &str) -> Result<Refres
         .await
         .context("Failed to parse token refresh response")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh_locks_are_shared_per_token() {
+        let first = refresh_lock("lock-test-token-a");
+        let again = refresh_lock("lock-test-token-a");
+        let other = refresh_lock("lock-test-token-b");
+
+        assert!(Arc::ptr_eq(&first, &again));
+        assert!(!Arc::ptr_eq(&first, &other));
+    }
+
+    #[test]
+    fn unused_refresh_locks_are_dropped() {
+        let lock = refresh_lock("lock-test-token-c");
+        let weak = Arc::downgrade(&lock);
+        drop(lock);
+
+        let _unrelated = refresh_lock("lock-test-token-d");
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[tokio::test]
+    async fn different_logins_refresh_in_parallel() {
+        let first = refresh_lock("lock-test-token-e");
+        let _held = first.lock().await;
+
+        let other = refresh_lock("lock-test-token-f");
+        assert!(other.try_lock().is_ok());
+        let same = refresh_lock("lock-test-token-e");
+        assert!(same.try_lock().is_err());
+    }
+}
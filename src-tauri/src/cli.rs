@@ -0,0 +1,103 @@
+//! Minimal command line interface for headless machines
+//!
+//! Usage:
+//!   codex-switcher login <name>            paste the redirect URL after logging in
+//!   codex-switcher login <name> --device   use the device authorization flow
+//!
+//! Logins reserve their account name the same way the GUI commands do.
+
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+use crate::commands::oauth::{
+    cancel_login, complete_login, complete_manual_login, start_device_session, start_manual_login,
+};
+
+const USAGE: &str = "Usage: codex-switcher login <name> [--device]";
+
+/// Run a CLI command if one was given on the command line.
+/// Returns the process exit code, or `None` to start the GUI.
+pub fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("login") {
+        return None;
+    }
+
+    let device = args.iter().any(|a| a == "--device");
+    let name = match args.iter().skip(1).find(|a| !a.starts_with("--")) {
+        Some(name) => name.clone(),
+        None => {
+            eprintln!("{USAGE}");
+            return Some(2);
+        }
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start async runtime: {e}");
+            return Some(1);
+        }
+    };
+
+    let result = if device {
+        runtime.block_on(login_with_device_code(name))
+    } else {
+        runtime.block_on(login_with_pasted_redirect(name))
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("Login failed: {e}");
+            Some(1)
+        }
+    }
+}
+
+async fn login_with_pasted_redirect(name: String) -> Result<(), String> {
+    let info = start_manual_login(name).await?;
+
+    println!(
+        "Open this URL in a browser on any machine and log in:\n\n{}\n",
+        info.auth_url
+    );
+    println!("The browser will then fail to load a localhost page. Copy the full URL");
+    print!("from its address bar (or just the `code` value) and paste it here: ");
+    std::io::stdout().flush().map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    let read = std::io::stdin().lock().read_line(&mut line);
+    let result = match read {
+        Ok(_) => complete_manual_login(line, Some(info.session_id.clone())).await,
+        Err(e) => Err(e.to_string()),
+    };
+    if result.is_err() {
+        cancel_login(Some(info.session_id)).await?;
+    }
+    let info = result?;
+
+    println!("Added and activated account '{}'", info.name);
+    Ok(())
+}
+
+async fn login_with_device_code(name: String) -> Result<(), String> {
+    let info = start_device_session(name, |_| Arc::new(|_, _| {})).await?;
+
+    println!(
+        "Open {} in a browser and enter the code: {}",
+        info.verification_uri, info.user_code
+    );
+    if let Some(complete) = &info.verification_uri_complete {
+        println!("Or open this link directly: {complete}");
+    }
+    println!(
+        "Waiting for approval (expires in {} seconds)...",
+        info.expires_in
+    );
+
+    let info = complete_login(Some(info.session_id)).await?;
+
+    println!("Added and activated account '{}'", info.name);
+    Ok(())
+}
@@ -1,11 +1,22 @@
 //! Account management Tauri commands
 
+use crate::api::{fetch_chatgpt_workspaces, remove_cached_usage};
 use crate::auth::{
-    add_account, create_chatgpt_account_from_refresh_token, get_active_account,
-    import_from_auth_json, load_accounts, remove_account, save_accounts, set_active_account,
+    add_account, clear_account_tokens, clear_active_account, clear_codex_auth,
+    create_chatgpt_account_from_refresh_token, get_account, get_active_account,
+    import_from_auth_json, load_accounts, migrate_accounts_store, remove_account,
+    revoke_account_tokens, save_accounts, set_account_workspaces, set_active_account,
     switch_to_account, touch_account,
 };
-use crate::types::{AccountInfo, AccountsStore, AuthData, ImportAccountsSummary, StoredAccount};
+use crate::notifications::{cancel_reset_notifications, clear_health_issues};
+use crate::process::terminate_processes;
+use crate::settings::load_settings;
+use crate::types::{
+    AccountInfo, AccountWorkspace, AccountsStore, AppServerRestartPolicy, AppServerRestartResult,
+    AuthData, ImportAccountsSummary, RevocationResult, StoredAccount, SwitchAccountResult,
+};
+#[cfg(unix)]
+use crate::{auth::get_codex_home, process};
 
 use anyhow::Context;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
@@ -21,6 +32,7 @@ use sha2::Sha256;
 use std::collections::HashSet;
 use std::fs;
 use std::io::{Read, Write};
+use std::time::Duration;
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
@@ -109,9 +121,9 @@ pub async fn add_account_from_file(path: String, name: String) -> Result<Account
     Ok(AccountInfo::from_stored(&stored, active_id))
 }
 
-/// Switch to a different account
+/// Switch to a different account and apply the app-server restart policy
 #[tauri::command]
-pub async fn switch_account(account_id: String) -> Result<(), String> {
+pub async fn switch_account(account_id: String) -> Result<SwitchAccountResult, String> {
     let store = load_accounts().map_err(|e| e.to_string())?;
 
     // Find the account
@@ -130,34 +142,170 @@ pub async fn switch_account(account_id: String) -> Result<(), String> {
     // Update last_used_at
     touch_account(&account_id).map_err(|e| e.to_string())?;
 
-    // Restart Antigravity background process if it is running
-    // This allows it to pick up the new authorization file seamlessly
-    if let Ok(pids) = find_antigravity_processes() {
-        for pid in pids {
-            #[cfg(unix)]
-            {
-                let _ = std::process::Command::new("kill")
-                    .arg("-9")
-                    .arg(pid.to_string())
-                    .output();
-            }
-            #[cfg(windows)]
-            {
-                let _ = std::process::Command::new("taskkill")
-                    .args(["/F", "/PID", &pid.to_string()])
-                    .output();
+    // IDE app-servers keep the previous credentials until they are restarted
+    let settings = load_settings().map(|s| s.processes).unwrap_or_default();
+    let mut result = SwitchAccountResult::default();
+    match settings.app_server_restart {
+        AppServerRestartPolicy::Never => {}
+        AppServerRestartPolicy::Ask => {
+            result.pending_restart_pids = find_antigravity_processes().unwrap_or_default();
+        }
+        AppServerRestartPolicy::Always => {
+            let pids = find_antigravity_processes().unwrap_or_default();
+            if !pids.is_empty() {
+                let timeout = Duration::from_secs(settings.terminate_timeout_secs);
+                result.restart = Some(terminate_processes(&pids, timeout).await);
             }
         }
     }
 
-    Ok(())
+    Ok(result)
+}
+
+/// Gracefully restart running IDE app-servers so they pick up the active account.
+/// Limited to `pids` when given (e.g. those reported by `switch_account`).
+#[tauri::command]
+pub async fn restart_ide_app_servers(
+    pids: Option<Vec<u32>>,
+) -> Result<AppServerRestartResult, String> {
+    let settings = load_settings().map_err(|e| e.to_string())?.processes;
+    let mut servers = find_antigravity_processes().map_err(|e| e.to_string())?;
+    if let Some(pids) = pids {
+        servers.retain(|pid| pids.contains(pid));
+    }
+
+    let timeout = Duration::from_secs(settings.terminate_timeout_secs);
+    Ok(terminate_processes(&servers, timeout).await)
 }
 
-/// Remove an account
+/// Remove an account, optionally revoking its refresh token first
 #[tauri::command]
-pub async fn delete_account(account_id: String) -> Result<(), String> {
+pub async fn delete_account(
+    account_id: String,
+    revoke: Option<bool>,
+) -> Result<RevocationResult, String> {
+    let revocation = sign_out_account(&account_id, revoke.unwrap_or(false)).await?;
     remove_account(&account_id).map_err(|e| e.to_string())?;
-    Ok(())
+    remove_cached_usage(&account_id);
+    cancel_reset_notifications(&account_id);
+    clear_health_issues(&account_id);
+    Ok(revocation)
+}
+
+/// Log an account out of Codex without removing it: optionally revoke its
+/// refresh token, and clear auth.json if it is the active account
+#[tauri::command]
+pub async fn logout_account(
+    account_id: String,
+    revoke: Option<bool>,
+) -> Result<RevocationResult, String> {
+    sign_out_account(&account_id, revoke.unwrap_or(false)).await
+}
+
+/// Shared part of delete and logout: revoke if requested, and clear auth.json
+/// plus the active account if this account is active
+async fn sign_out_account(account_id: &str, revoke: bool) -> Result<RevocationResult, String> {
+    let store = load_accounts().map_err(|e| e.to_string())?;
+    let account = store
+        .accounts
+        .iter()
+        .find(|a| a.id == account_id)
+        .ok_or_else(|| format!("Account not found: {account_id}"))?;
+
+    let revocation = if revoke && shares_login_with_other_account(&store, account) {
+        // Revoking would also sign out the other workspace accounts of this login
+        RevocationResult {
+            requested: true,
+            revoked: false,
+            error: Some("Refresh token is shared with other workspace accounts".to_string()),
+        }
+    } else if revoke {
+        revoke_account_tokens(account).await
+    } else {
+        RevocationResult::default()
+    };
+
+    // Revoked tokens are useless; keep the account but signed out
+    if revocation.revoked {
+        clear_account_tokens(account_id).map_err(|e| e.to_string())?;
+    }
+
+    if store.active_account_id.as_deref() == Some(account_id) {
+        clear_codex_auth().map_err(|e| e.to_string())?;
+        clear_active_account().map_err(|e| e.to_string())?;
+    }
+
+    Ok(revocation)
+}
+
+/// Whether another stored account uses the same ChatGPT refresh token
+fn shares_login_with_other_account(store: &AccountsStore, account: &StoredAccount) -> bool {
+    let AuthData::ChatGPT { refresh_token, .. } = &account.auth_data else {
+        return false;
+    };
+    store.accounts.iter().any(|other| {
+        other.id != account.id
+            && matches!(&other.auth_data, AuthData::ChatGPT { refresh_token: other_token, .. } if other_token == refresh_token)
+    })
+}
+
+/// List the workspaces available to a ChatGPT account's login
+#[tauri::command]
+pub async fn list_account_workspaces(account_id: String) -> Result<Vec<AccountWorkspace>, String> {
+    let account = get_account(&account_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account not found: {account_id}"))?;
+
+    match &account.auth_data {
+        AuthData::ChatGPT { .. } => Ok(refresh_workspaces(&account).await),
+        AuthData::ApiKey { .. } => Ok(Vec::new()),
+    }
+}
+
+/// Fetch and store the login's workspaces, falling back to the stored ones
+async fn refresh_workspaces(account: &StoredAccount) -> Vec<AccountWorkspace> {
+    match fetch_chatgpt_workspaces(account).await {
+        Ok(workspaces) if !workspaces.is_empty() => {
+            if let Err(e) = set_account_workspaces(&account.id, workspaces.clone()) {
+                println!(
+                    "[Workspaces] Failed to store workspaces for {}: {e:#}",
+                    account.name
+                );
+            }
+            workspaces
+        }
+        Ok(_) => account.workspaces.clone(),
+        Err(e) => {
+            println!(
+                "[Workspaces] Failed to fetch workspaces for {}: {e:#}",
+                account.name
+            );
+            account.workspaces.clone()
+        }
+    }
+}
+
+/// Add an account that uses an existing login but is pinned to another workspace
+#[tauri::command]
+pub async fn add_workspace_account(
+    account_id: String,
+    workspace_id: String,
+    name: String,
+) -> Result<AccountInfo, String> {
+    let source = get_account(&account_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account not found: {account_id}"))?;
+    if !source.workspaces.iter().any(|w| w.id == workspace_id) {
+        refresh_workspaces(&source).await;
+    }
+
+    let account = crate::auth::storage::add_workspace_account(&account_id, &workspace_id, name)
+        .map_err(|e| e.to_string())?;
+    let store = load_accounts().map_err(|e| e.to_string())?;
+    Ok(AccountInfo::from_stored(
+        &account,
+        store.active_account_id.as_deref(),
+    ))
 }
 
 /// Rename an account
@@ -235,35 +383,11 @@ fn find_antigravity_processes() -> anyhow::Result<Vec<u32>> {
 
     #[cfg(unix)]
     {
-        // Use ps with custom format to get the pid and full command line
-        let output = std::process::Command::new("ps")
-            .args(["-eo", "pid,command"])
-            .output()?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines().skip(1) {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            if let Some((pid_str, command)) = line.split_once(' ') {
-                let pid_str = pid_str.trim();
-                let command = command.trim();
-
-                // Antigravity processes have a specific path format
-                let is_antigravity = (command.contains(".antigravity/extensions/openai.chatgpt")
-                    || command.contains(".vscode/extensions/openai.chatgpt"))
-                    && (command.ends_with("codex app-server --analytics-default-enabled")
-                        || command.contains("/codex app-server"));
-
-                if is_antigravity {
-                    if let Ok(pid) = pid_str.parse::<u32>() {
-                        pids.push(pid);
-                    }
-                }
-            }
-        }
+        // Only servers reading the auth.json we just rewrote need a restart
+        let codex_home = get_codex_home()?;
+        let servers =
+            process::find_ide_app_servers(process::default_scanner().as_ref(), &codex_home)?;
+        pids.extend(servers.iter().map(|p| p.pid));
     }
 
     #[cfg(windows)]
@@ -641,8 +765,9 @@ fn validate_imported_store(store: &AccountsStore) -> anyhow::Result<()> {
 
 fn merge_accounts_store(
     mut current: AccountsStore,
-    imported: AccountsStore,
+    mut imported: AccountsStore,
 ) -> (AccountsStore, ImportAccountsSummary) {
+    migrate_accounts_store(&mut imported);
     let imported_version = imported.version;
     let imported_active_id = imported.active_account_id;
     let total_in_payload = imported.accounts.len();
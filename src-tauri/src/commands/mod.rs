@@ -4,10 +4,12 @@ pub mod account;
 pub mod notifications;
 pub mod oauth;
 pub mod process;
+pub mod settings;
 pub mod usage;
 
 pub use account::*;
 pub use notifications::*;
 pub use oauth::*;
 pub use process::*;
+pub use settings::*;
 pub use usage::*;
@@ -1,45 +1,38 @@
 //! Notification settings commands
 
 use crate::auth::storage::{load_accounts, save_accounts};
-use crate::types::{LastNotifications, NotificationSettings};
+use crate::notifications::{
+    cancel_reset_notifications, deliver_notification, global_notification_settings,
+    list_health_issues, load_notification_history, validate_notification_settings,
+    NotificationMessage,
+};
+use crate::types::{
+    AccountHealthIssue, LastNotifications, NotificationHistoryEntry, NotificationOverrides, NotificationSettings,
+    SinkDeliveryResult,
+};
 
-/// Validate notification settings
+/// Validate the settings an account ends up with
 fn validate_settings(settings: &NotificationSettings) -> Result<(), String> {
-    if let Some(threshold) = settings.primary_threshold {
-        if threshold > 100 {
-            return Err("primary_threshold must be between 0 and 100".to_string());
-        }
-    }
-    if let Some(threshold) = settings.secondary_threshold {
-        if threshold > 100 {
-            return Err("secondary_threshold must be between 0 and 100".to_string());
-        }
-    }
-    if let Some(threshold) = settings.credits_threshold {
-        if threshold > 100 {
-            return Err("credits_threshold must be between 0 and 100".to_string());
-        }
-    }
-    if settings.min_interval_minutes < 1 {
-        return Err("min_interval_minutes must be at least 1".to_string());
-    }
-    // Note: min_interval_minutes is u8, max value is 255, which is ~4.25 hours
-    // This is a reasonable maximum for a notification cooldown
-    Ok(())
+    validate_notification_settings(settings).map_err(|e| format!("{e:#}"))
 }
 
-/// Update notification settings for an account
+/// Update notification settings for an account.
+/// Fields equal to the global defaults are stored as inherited.
 #[tauri::command]
 pub async fn update_notification_settings(
     account_id: String,
     settings: NotificationSettings,
 ) -> Result<(), String> {
     validate_settings(&settings)?;
-    
+    let defaults = global_notification_settings().defaults;
+
     let mut store = load_accounts().map_err(|e| e.to_string())?;
 
     if let Some(account) = store.accounts.iter_mut().find(|a| a.id == account_id) {
-        account.notification_settings = settings;
+        if !settings.enabled || !settings.notify_on_reset {
+            cancel_reset_notifications(&account_id);
+        }
+        account.notification_settings = NotificationOverrides::diff(&settings, &defaults);
         save_accounts(&store).map_err(|e| e.to_string())?;
         Ok(())
     } else {
@@ -47,12 +40,27 @@ pub async fn update_notification_settings(
     }
 }
 
-/// Get notification settings for an account
+/// Get the effective notification settings for an account
 #[tauri::command]
 pub async fn get_notification_settings(
     account_id: String,
 ) -> Result<NotificationSettings, String> {
     let store = load_accounts().map_err(|e| e.to_string())?;
+    let defaults = global_notification_settings().defaults;
+
+    if let Some(account) = store.accounts.iter().find(|a| a.id == account_id) {
+        Ok(account.notification_settings.resolve(&defaults))
+    } else {
+        Err(format!("Account not found: {}", account_id))
+    }
+}
+
+/// Get the fields an account overrides; everything else is inherited
+#[tauri::command]
+pub async fn get_notification_overrides(
+    account_id: String,
+) -> Result<NotificationOverrides, String> {
+    let store = load_accounts().map_err(|e| e.to_string())?;
 
     if let Some(account) = store.accounts.iter().find(|a| a.id == account_id) {
         Ok(account.notification_settings.clone())
@@ -61,6 +69,67 @@ pub async fn get_notification_settings(
     }
 }
 
+/// Replace the overrides of an account; missing fields inherit the global defaults
+#[tauri::command]
+pub async fn update_notification_overrides(
+    account_id: String,
+    overrides: NotificationOverrides,
+) -> Result<(), String> {
+    let settings = overrides.resolve(&global_notification_settings().defaults);
+    validate_settings(&settings)?;
+
+    let mut store = load_accounts().map_err(|e| e.to_string())?;
+
+    if let Some(account) = store.accounts.iter_mut().find(|a| a.id == account_id) {
+        if !settings.enabled || !settings.notify_on_reset {
+            cancel_reset_notifications(&account_id);
+        }
+        account.notification_settings = overrides;
+        save_accounts(&store).map_err(|e| e.to_string())?;
+        Ok(())
+    } else {
+        Err(format!("Account not found: {}", account_id))
+    }
+}
+
+/// Apply notification settings to every account at once.
+/// The given fields are merged into each account's overrides; with `replace`
+/// they replace them, so an empty object makes every account inherit the defaults.
+/// Returns the number of accounts updated.
+#[tauri::command]
+pub async fn apply_notification_settings_to_all(
+    overrides: NotificationOverrides,
+    replace: Option<bool>,
+) -> Result<usize, String> {
+    let defaults = global_notification_settings().defaults;
+    let mut store = load_accounts().map_err(|e| e.to_string())?;
+
+    let mut updated = Vec::with_capacity(store.accounts.len());
+    for account in &store.accounts {
+        let mut account_overrides = if replace.unwrap_or(false) {
+            NotificationOverrides::default()
+        } else {
+            account.notification_settings.clone()
+        };
+        account_overrides.merge(&overrides);
+
+        let settings = account_overrides.resolve(&defaults);
+        validate_settings(&settings).map_err(|e| format!("{}: {}", account.name, e))?;
+        updated.push((account_overrides, settings));
+    }
+
+    // Only save once every account validated
+    for (account, (account_overrides, settings)) in store.accounts.iter_mut().zip(updated) {
+        if !settings.enabled || !settings.notify_on_reset {
+            cancel_reset_notifications(&account.id);
+        }
+        account.notification_settings = account_overrides;
+    }
+    save_accounts(&store).map_err(|e| e.to_string())?;
+
+    Ok(store.accounts.len())
+}
+
 /// Reset last notification timestamps for an account (e.g., when thresholds are changed)
 #[tauri::command]
 pub async fn reset_notification_history(account_id: String) -> Result<(), String> {
@@ -74,3 +143,64 @@ pub async fn reset_notification_history(account_id: String) -> Result<(), String
         Err(format!("Account not found: {}", account_id))
     }
 }
+
+/// Send a test notification to every sink configured for an account,
+/// or to the global sinks when no account is given
+#[tauri::command]
+pub async fn send_test_notification(
+    app: tauri::AppHandle,
+    account_id: Option<String>,
+) -> Result<Vec<SinkDeliveryResult>, String> {
+    let (account_name, settings) = match &account_id {
+        Some(account_id) => {
+            let store = load_accounts().map_err(|e| e.to_string())?;
+            let account = store
+                .accounts
+                .into_iter()
+                .find(|a| &a.id == account_id)
+                .ok_or_else(|| format!("Account not found: {}", account_id))?;
+            let settings = account
+                .notification_settings
+                .resolve(&global_notification_settings().defaults);
+            (account.name, settings)
+        }
+        None => ("Test".to_string(), global_notification_settings().defaults),
+    };
+
+    let mut message = NotificationMessage::new(
+        &account_name,
+        "test",
+        "Test notification - delivery is working".to_string(),
+    );
+    message.account_id = account_id;
+    Ok(deliver_notification(&app, &settings, &message).await)
+}
+
+/// List recorded notifications, newest first.
+/// Limited to one account when `account_id` is given.
+#[tauri::command]
+pub async fn list_notification_history(
+    account_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<NotificationHistoryEntry>, String> {
+    let account = match account_id {
+        Some(account_id) => {
+            let store = load_accounts().map_err(|e| e.to_string())?;
+            let account = store
+                .accounts
+                .into_iter()
+                .find(|a| a.id == account_id)
+                .ok_or_else(|| format!("Account not found: {}", account_id))?;
+            Some(account)
+        }
+        None => None,
+    };
+
+    load_notification_history(account.as_ref(), limit).map_err(|e| e.to_string())
+}
+
+/// List failing account health checks, optionally for one account only
+#[tauri::command]
+pub fn get_account_health(account_id: Option<String>) -> Result<Vec<AccountHealthIssue>, String> {
+    Ok(list_health_issues(account_id.as_deref()))
+}
@@ -1,61 +1,263 @@
 //! OAuth login Tauri commands
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use tauri::{AppHandle, Emitter};
 use tokio::sync::oneshot;
+use uuid::Uuid;
 
-use crate::auth::oauth_server::{start_oauth_login, wait_for_oauth_login, OAuthLoginResult};
+use crate::auth::oauth_server::{
+    complete_manual_oauth_login, start_device_oauth_login, start_manual_oauth_login,
+    start_oauth_login, wait_for_oauth_login, CancellationToken, LoginProgress, ManualOAuthLogin,
+    OAuthLoginResult,
+};
 use crate::auth::{
     add_account, load_accounts, set_active_account, switch_to_account, touch_account,
 };
-use crate::types::{AccountInfo, OAuthLoginInfo};
+use crate::types::{
+    AccountInfo, DeviceLoginInfo, LoginFlow, OAuthLoginInfo, OAuthProgressEvent, PendingLoginInfo,
+    StoredAccount,
+};
+
+/// Event emitted to the frontend as a login progresses
+const OAUTH_PROGRESS_EVENT: &str = "oauth-login-progress";
 
 struct PendingOAuth {
-    rx: oneshot::Receiver<anyhow::Result<OAuthLoginResult>>,
-    cancelled: Arc<AtomicBool>,
+    /// Taken by `complete_login` while it waits for the result
+    rx: Option<LoginResultReceiver>,
+    cancel: CancellationToken,
 }
 
-// Global state for pending OAuth login
-static PENDING_OAUTH: Mutex<Option<PendingOAuth>> = Mutex::new(None);
+type LoginResultReceiver = oneshot::Receiver<anyhow::Result<OAuthLoginResult>>;
 
-/// Start the OAuth login flow
-#[tauri::command]
-pub async fn start_login(account_name: String) -> Result<OAuthLoginInfo, String> {
-    // Cancel any previous pending flow so it does not keep the callback port occupied.
-    if let Some(previous) = {
-        let mut pending = PENDING_OAUTH.lock().unwrap();
-        pending.take()
-    } {
-        previous.cancelled.store(true, Ordering::Relaxed);
-    }
+enum PendingFlow {
+    /// Name reserved while the flow is being started
+    Starting,
+    /// Browser callback or device flow, resolved by a background task
+    Waiting(PendingOAuth),
+    /// Headless flow awaiting a pasted redirect URL
+    Manual(ManualOAuthLogin),
+    /// Background flow that succeeded before `complete_login` collected it
+    Finished {
+        account: Box<StoredAccount>,
+        finished_at: DateTime<Utc>,
+    },
+}
 
-    let (info, rx, cancelled) = start_oauth_login(account_name)
-        .await
-        .map_err(|e| e.to_string())?;
+struct PendingLogin {
+    account_name: String,
+    kind: LoginFlow,
+    callback_port: Option<u16>,
+    started_at: DateTime<Utc>,
+    flow: PendingFlow,
+}
 
-    // Store the receiver for later
-    {
-        let mut pending = PENDING_OAUTH.lock().unwrap();
-        *pending = Some(PendingOAuth { rx, cancelled });
+impl PendingLogin {
+    fn cancel(&self) {
+        if let PendingFlow::Waiting(pending) = &self.flow {
+            pending.cancel.cancel();
+        }
     }
 
-    Ok(info)
+    fn info(&self, session_id: &str) -> PendingLoginInfo {
+        PendingLoginInfo {
+            session_id: session_id.to_string(),
+            account_name: self.account_name.clone(),
+            flow: self.kind,
+            callback_port: self.callback_port,
+            started_at: self.started_at,
+        }
+    }
 }
 
-/// Wait for the OAuth login to complete and add the account
-#[tauri::command]
-pub async fn complete_login() -> Result<AccountInfo, String> {
-    let pending = {
-        let mut pending = PENDING_OAUTH.lock().unwrap();
-        pending
-            .take()
-            .ok_or_else(|| "No pending OAuth login".to_string())?
+// Global state for pending OAuth logins, keyed by session ID
+static PENDING_LOGINS: Mutex<BTreeMap<String, PendingLogin>> = Mutex::new(BTreeMap::new());
+
+/// Headless logins and finished but uncollected logins hold no resources
+/// and are dropped once this old
+const LOGIN_TTL_MINUTES: i64 = 30;
+
+/// Drop headless logins that were never completed and finished logins that
+/// were never collected
+fn prune_expired(pending: &mut BTreeMap<String, PendingLogin>) {
+    let cutoff = Utc::now() - Duration::minutes(LOGIN_TTL_MINUTES);
+    pending.retain(|_, login| match &login.flow {
+        PendingFlow::Finished { finished_at, .. } => *finished_at > cutoff,
+        PendingFlow::Manual(_) => login.started_at > cutoff,
+        PendingFlow::Starting | PendingFlow::Waiting(_) => true,
+    });
+}
+
+/// Reserve a session for a new login. The name is checked and reserved under
+/// one lock, so two logins for the same name cannot both start. A pending
+/// headless login for the name is replaced, as it may have been abandoned.
+fn reserve_login(account_name: &str, kind: LoginFlow) -> Result<String, String> {
+    let mut pending = PENDING_LOGINS.lock().unwrap();
+    prune_expired(&mut pending);
+    pending
+        .retain(|_, login| login.account_name != account_name || login.kind != LoginFlow::Manual);
+    if pending.values().any(|p| p.account_name == account_name) {
+        return Err(format!(
+            "A login for account '{account_name}' is already in progress"
+        ));
+    }
+
+    let session_id = Uuid::new_v4().to_string();
+    let login = PendingLogin {
+        account_name: account_name.to_string(),
+        kind,
+        callback_port: None,
+        started_at: Utc::now(),
+        flow: PendingFlow::Starting,
     };
+    pending.insert(session_id.clone(), login);
+    Ok(session_id)
+}
 
-    let account = wait_for_oauth_login(pending.rx)
+/// Release a reservation whose flow failed to start
+fn release_login(session_id: &str) {
+    PENDING_LOGINS.lock().unwrap().remove(session_id);
+}
+
+/// Attach a started flow to its reservation. Fails when the login was
+/// cancelled while it was starting.
+fn register_login(
+    session_id: &str,
+    callback_port: Option<u16>,
+    flow: PendingFlow,
+) -> Result<(), String> {
+    let mut pending = PENDING_LOGINS.lock().unwrap();
+    match pending.get_mut(session_id) {
+        Some(login) => {
+            login.callback_port = callback_port;
+            login.flow = flow;
+            Ok(())
+        }
+        None => {
+            if let PendingFlow::Waiting(oauth) = &flow {
+                oauth.cancel.cancel();
+            }
+            Err("Login was cancelled".to_string())
+        }
+    }
+}
+
+/// Register a browser callback or device flow and watch it in the background
+fn register_waiting_login(
+    session_id: &str,
+    callback_port: Option<u16>,
+    rx: LoginResultReceiver,
+    cancel: CancellationToken,
+) -> Result<(), String> {
+    let (tx, forwarded) = oneshot::channel();
+    register_login(
+        session_id,
+        callback_port,
+        PendingFlow::Waiting(PendingOAuth {
+            rx: Some(forwarded),
+            cancel,
+        }),
+    )?;
+    tokio::spawn(watch_login(session_id.to_string(), rx, tx));
+    Ok(())
+}
+
+/// Wait for a background flow to end. The result is passed on when
+/// `complete_login` is waiting for it. Otherwise a successful login is kept
+/// on the session until it is collected, cancelled or expires, and a failed
+/// or timed-out one is dropped so it does not keep its name reserved.
+async fn watch_login(
+    session_id: String,
+    rx: LoginResultReceiver,
+    tx: oneshot::Sender<anyhow::Result<OAuthLoginResult>>,
+) {
+    let result = rx
         .await
-        .map_err(|e| e.to_string())?;
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("OAuth login was cancelled")));
+
+    let collected = matches!(
+        PENDING_LOGINS.lock().unwrap().get(&session_id).map(|p| &p.flow),
+        Some(PendingFlow::Waiting(oauth)) if oauth.rx.is_none()
+    );
+    // The receiver is gone when `complete_login` stopped waiting
+    let result = if collected {
+        match tx.send(result) {
+            Ok(()) => return,
+            Err(result) => result,
+        }
+    } else {
+        result
+    };
 
+    let mut pending = PENDING_LOGINS.lock().unwrap();
+    let Some(login) = pending.get_mut(&session_id) else {
+        // Cancelled in the meantime
+        return;
+    };
+    match result {
+        Ok(result) => {
+            println!("[OAuth] Login {session_id} finished, waiting for it to be completed");
+            login.flow = PendingFlow::Finished {
+                account: Box::new(result.account),
+                finished_at: Utc::now(),
+            };
+        }
+        Err(e) => {
+            println!("[OAuth] Login {session_id} ended: {e:#}");
+            pending.remove(&session_id);
+        }
+    }
+}
+
+/// Build a progress callback that emits events for one login session
+fn progress_emitter(app: AppHandle, session_id: String) -> LoginProgress {
+    Arc::new(move |stage, message| {
+        let event = OAuthProgressEvent {
+            session_id: session_id.clone(),
+            stage,
+            message: message.map(String::from),
+        };
+        if let Err(e) = app.emit(OAUTH_PROGRESS_EVENT, event) {
+            println!("[OAuth] Failed to emit progress event: {e}");
+        }
+    })
+}
+
+/// Resolve the session to act on. When no ID is given, fall back to the only
+/// pending login of the requested kind (keeps single-login callers working).
+fn resolve_session_id(
+    pending: &BTreeMap<String, PendingLogin>,
+    session_id: Option<String>,
+    manual: bool,
+) -> Result<String, String> {
+    if let Some(id) = session_id {
+        return if pending.contains_key(&id) {
+            Ok(id)
+        } else {
+            Err(format!("No pending OAuth login: {id}"))
+        };
+    }
+
+    let mut candidates = pending
+        .iter()
+        .filter(|(_, p)| match p.flow {
+            PendingFlow::Starting => false,
+            PendingFlow::Waiting(_) | PendingFlow::Finished { .. } => !manual,
+            PendingFlow::Manual(_) => manual,
+        })
+        .map(|(id, _)| id.clone());
+
+    match (candidates.next(), candidates.next()) {
+        (Some(id), None) => Ok(id),
+        (None, _) => Err("No pending OAuth login".to_string()),
+        (Some(_), Some(_)) => Err("Multiple logins are pending; specify a session ID".to_string()),
+    }
+}
+
+/// Add a freshly logged-in account, make it active and return its info
+fn finish_login(account: StoredAccount) -> Result<AccountInfo, String> {
     // Add the account to storage
     let stored = add_account(account).map_err(|e| e.to_string())?;
 
@@ -70,12 +272,337 @@ pub async fn complete_login() -> Result<AccountInfo, String> {
     Ok(AccountInfo::from_stored(&stored, active_id))
 }
 
-/// Cancel a pending OAuth login
+/// Start the OAuth login flow.
+/// Progress is reported through `oauth-login-progress` events.
+#[tauri::command]
+pub async fn start_login(
+    app: AppHandle,
+    account_name: String,
+    open_browser: Option<bool>,
+) -> Result<OAuthLoginInfo, String> {
+    let session_id = reserve_login(&account_name, LoginFlow::Callback)?;
+    let progress = progress_emitter(app, session_id.clone());
+    let started = start_oauth_login(account_name, open_browser.unwrap_or(false), progress).await;
+    let (mut info, rx, cancel) = started.map_err(|e| {
+        release_login(&session_id);
+        e.to_string()
+    })?;
+
+    register_waiting_login(&session_id, Some(info.callback_port), rx, cancel)?;
+    info.session_id = session_id;
+
+    Ok(info)
+}
+
+/// Start the device authorization login flow.
+/// Finish it with `complete_login` once the user approved the code.
 #[tauri::command]
-pub async fn cancel_login() -> Result<(), String> {
-    let mut pending = PENDING_OAUTH.lock().unwrap();
-    if let Some(pending_oauth) = pending.take() {
-        pending_oauth.cancelled.store(true, Ordering::Relaxed);
+pub async fn start_device_login(
+    app: AppHandle,
+    account_name: String,
+) -> Result<DeviceLoginInfo, String> {
+    start_device_session(account_name, |session_id| {
+        progress_emitter(app, session_id.to_string())
+    })
+    .await
+}
+
+/// Reserve a session and start a device login in it (shared with the CLI)
+pub(crate) async fn start_device_session(
+    account_name: String,
+    progress: impl FnOnce(&str) -> LoginProgress,
+) -> Result<DeviceLoginInfo, String> {
+    let session_id = reserve_login(&account_name, LoginFlow::Device)?;
+    let progress = progress(&session_id);
+    let started = start_device_oauth_login(account_name, progress).await;
+    let (mut info, rx, cancel) = started.map_err(|e| {
+        release_login(&session_id);
+        e.to_string()
+    })?;
+
+    register_waiting_login(&session_id, None, rx, cancel)?;
+    info.session_id = session_id;
+
+    Ok(info)
+}
+
+/// Wait for an OAuth login to complete and add the account.
+/// Returns right away when the login already finished.
+#[tauri::command]
+pub async fn complete_login(session_id: Option<String>) -> Result<AccountInfo, String> {
+    let (session_id, rx) = {
+        let mut pending = PENDING_LOGINS.lock().unwrap();
+        prune_expired(&mut pending);
+        let session_id = resolve_session_id(&pending, session_id, false)?;
+        let finished = pending
+            .get(&session_id)
+            .is_some_and(|p| matches!(p.flow, PendingFlow::Finished { .. }));
+        if finished {
+            if let Some(PendingLogin {
+                flow: PendingFlow::Finished { account, .. },
+                ..
+            }) = pending.remove(&session_id)
+            {
+                drop(pending);
+                return finish_login(*account);
+            }
+        }
+
+        let rx = match pending.get_mut(&session_id).map(|p| &mut p.flow) {
+            Some(PendingFlow::Waiting(oauth)) => oauth.rx.take(),
+            _ => None,
+        };
+        let rx = rx.ok_or_else(|| format!("Login {session_id} is already being completed"))?;
+        (session_id, rx)
+    };
+
+    let result = wait_for_oauth_login(rx).await;
+    PENDING_LOGINS.lock().unwrap().remove(&session_id);
+
+    let account = result.map_err(|e| e.to_string())?;
+    finish_login(account)
+}
+
+/// Start a headless login flow without a local callback server
+#[tauri::command]
+pub async fn start_manual_login(account_name: String) -> Result<OAuthLoginInfo, String> {
+    let session_id = reserve_login(&account_name, LoginFlow::Manual)?;
+    let (mut info, pending) = start_manual_oauth_login(account_name).map_err(|e| {
+        release_login(&session_id);
+        e.to_string()
+    })?;
+
+    register_login(&session_id, None, PendingFlow::Manual(pending))?;
+    info.session_id = session_id;
+
+    Ok(info)
+}
+
+/// Finish a headless login with the pasted redirect URL (or authorization code)
+#[tauri::command]
+pub async fn complete_manual_login(
+    callback: String,
+    session_id: Option<String>,
+) -> Result<AccountInfo, String> {
+    let (session_id, login) = {
+        let mut pending = PENDING_LOGINS.lock().unwrap();
+        prune_expired(&mut pending);
+        let session_id = resolve_session_id(&pending, session_id, true)?;
+        let login = match pending.get(&session_id).map(|p| &p.flow) {
+            Some(PendingFlow::Manual(login)) => login.clone(),
+            _ => return Err(format!("Login {session_id} is not a headless login")),
+        };
+        (session_id, login)
+    };
+
+    let account = complete_manual_oauth_login(&login, &callback)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Only consume the pending login once it succeeded, so a typo can be retried
+    PENDING_LOGINS.lock().unwrap().remove(&session_id);
+
+    finish_login(account)
+}
+
+/// List logins that have been started but not completed
+#[tauri::command]
+pub async fn list_pending_logins() -> Result<Vec<PendingLoginInfo>, String> {
+    let mut pending = PENDING_LOGINS.lock().unwrap();
+    prune_expired(&mut pending);
+    Ok(pending.iter().map(|(id, login)| login.info(id)).collect())
+}
+
+/// Cancel a pending OAuth login, or all of them when no session ID is given
+#[tauri::command]
+pub async fn cancel_login(session_id: Option<String>) -> Result<(), String> {
+    let mut pending = PENDING_LOGINS.lock().unwrap();
+    match session_id {
+        Some(id) => {
+            if let Some(login) = pending.remove(&id) {
+                login.cancel();
+            }
+        }
+        None => {
+            for login in pending.values() {
+                login.cancel();
+            }
+            pending.clear();
+        }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_pending(session_id: &str) -> bool {
+        PENDING_LOGINS.lock().unwrap().contains_key(session_id)
+    }
+
+    #[test]
+    fn reserving_a_pending_name_fails() {
+        let first = reserve_login("reserve-twice", LoginFlow::Callback).unwrap();
+        assert!(reserve_login("reserve-twice", LoginFlow::Device).is_err());
+
+        release_login(&first);
+        let second = reserve_login("reserve-twice", LoginFlow::Device).unwrap();
+        release_login(&second);
+    }
+
+    #[test]
+    fn pending_manual_login_does_not_block_the_name() {
+        let manual = reserve_login("manual-abandoned", LoginFlow::Manual).unwrap();
+        let callback = reserve_login("manual-abandoned", LoginFlow::Callback).unwrap();
+
+        assert!(!is_pending(&manual));
+        assert!(is_pending(&callback));
+        release_login(&callback);
+    }
+
+    #[test]
+    fn expired_manual_logins_are_pruned() {
+        let session_id = reserve_login("manual-expired", LoginFlow::Manual).unwrap();
+        let (_, login) = start_manual_oauth_login("manual-expired".to_string()).unwrap();
+        register_login(&session_id, None, PendingFlow::Manual(login)).unwrap();
+        {
+            let mut pending = PENDING_LOGINS.lock().unwrap();
+            pending.get_mut(&session_id).unwrap().started_at =
+                Utc::now() - Duration::minutes(LOGIN_TTL_MINUTES + 1);
+            prune_expired(&mut pending);
+        }
+        assert!(!is_pending(&session_id));
+    }
+
+    #[tokio::test]
+    async fn failed_background_login_releases_the_name() {
+        let session_id = reserve_login("background-failed", LoginFlow::Callback).unwrap();
+        let (tx, rx) = oneshot::channel();
+        register_waiting_login(&session_id, Some(1455), rx, CancellationToken::new()).unwrap();
+        assert!(is_pending(&session_id));
+
+        assert!(tx.send(Err(anyhow::anyhow!("timed out"))).is_ok());
+        wait_until(|| !is_pending(&session_id)).await;
+
+        assert!(!is_pending(&session_id));
+        let next = reserve_login("background-failed", LoginFlow::Callback).unwrap();
+        release_login(&next);
+    }
+
+    async fn wait_until(done: impl Fn() -> bool) {
+        for _ in 0..50 {
+            if done() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    fn is_finished(session_id: &str) -> bool {
+        PENDING_LOGINS
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .is_some_and(|p| matches!(p.flow, PendingFlow::Finished { .. }))
+    }
+
+    fn login_result(name: &str) -> anyhow::Result<OAuthLoginResult> {
+        Ok(OAuthLoginResult {
+            account: StoredAccount::new_api_key(name.to_string(), "sk".to_string()),
+        })
+    }
+
+    #[tokio::test]
+    async fn uncollected_login_is_kept_until_it_expires() {
+        let session_id = reserve_login("background-uncollected", LoginFlow::Device).unwrap();
+        let (tx, rx) = oneshot::channel();
+        register_waiting_login(&session_id, None, rx, CancellationToken::new()).unwrap();
+
+        assert!(tx.send(login_result("background-uncollected")).is_ok());
+        wait_until(|| is_finished(&session_id)).await;
+
+        assert!(is_finished(&session_id));
+        assert!(reserve_login("background-uncollected", LoginFlow::Device).is_err());
+        {
+            let pending = PENDING_LOGINS.lock().unwrap();
+            let found = resolve_session_id(&pending, Some(session_id.clone()), false);
+            assert_eq!(found, Ok(session_id.clone()));
+        }
+
+        {
+            let mut pending = PENDING_LOGINS.lock().unwrap();
+            if let Some(PendingFlow::Finished { finished_at, .. }) =
+                pending.get_mut(&session_id).map(|p| &mut p.flow)
+            {
+                *finished_at = Utc::now() - Duration::minutes(LOGIN_TTL_MINUTES + 1);
+            }
+            prune_expired(&mut pending);
+        }
+        assert!(!is_pending(&session_id));
+    }
+
+    #[tokio::test]
+    async fn login_is_kept_when_the_collector_stops_waiting() {
+        let session_id = reserve_login("background-abandoned", LoginFlow::Callback).unwrap();
+        let (tx, rx) = oneshot::channel();
+        register_waiting_login(&session_id, Some(1455), rx, CancellationToken::new()).unwrap();
+
+        let forwarded = match &mut PENDING_LOGINS
+            .lock()
+            .unwrap()
+            .get_mut(&session_id)
+            .unwrap()
+            .flow
+        {
+            PendingFlow::Waiting(oauth) => oauth.rx.take().unwrap(),
+            _ => panic!("expected a waiting login"),
+        };
+        drop(forwarded);
+
+        assert!(tx.send(login_result("background-abandoned")).is_ok());
+        wait_until(|| is_finished(&session_id)).await;
+
+        assert!(is_finished(&session_id));
+        release_login(&session_id);
+    }
+
+    #[tokio::test]
+    async fn collected_login_receives_the_result() {
+        let session_id = reserve_login("background-collected", LoginFlow::Device).unwrap();
+        let (tx, rx) = oneshot::channel();
+        register_waiting_login(&session_id, None, rx, CancellationToken::new()).unwrap();
+
+        let forwarded = match &mut PENDING_LOGINS
+            .lock()
+            .unwrap()
+            .get_mut(&session_id)
+            .unwrap()
+            .flow
+        {
+            PendingFlow::Waiting(oauth) => oauth.rx.take().unwrap(),
+            _ => panic!("expected a waiting login"),
+        };
+        assert!(tx.send(Err(anyhow::anyhow!("denied"))).is_ok());
+
+        match forwarded.await {
+            Ok(Err(error)) => assert_eq!(error.to_string(), "denied"),
+            _ => panic!("expected the forwarded error"),
+        }
+        // `complete_login` removes the session once it has the result
+        assert!(is_pending(&session_id));
+        release_login(&session_id);
+    }
+
+    #[tokio::test]
+    async fn cancelling_while_starting_cancels_the_flow() {
+        let session_id = reserve_login("cancel-while-starting", LoginFlow::Callback).unwrap();
+        cancel_login(Some(session_id.clone())).await.unwrap();
+
+        let cancel = CancellationToken::new();
+        let (_tx, rx) = oneshot::channel();
+        assert!(register_waiting_login(&session_id, Some(1455), rx, cancel.clone()).is_err());
+        assert!(cancel.is_cancelled());
+        assert!(!is_pending(&session_id));
+    }
+}
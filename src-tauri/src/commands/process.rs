@@ -1,9 +1,14 @@
 //! Process detection commands
 
-use std::process::Command;
+#[cfg(unix)]
+use crate::auth::get_codex_home;
+#[cfg(unix)]
+use crate::process;
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
+#[cfg(windows)]
+use std::process::Command;
 
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
@@ -37,62 +42,18 @@ pub async fn check_codex_processes() -> Result<CodexProcessInfo, String> {
 
 /// Find all running codex processes. Returns (active_pids, background_count)
 fn find_codex_processes() -> anyhow::Result<(Vec<u32>, usize)> {
-    let mut pids = Vec::new();
-    #[allow(unused_mut)]
-    let mut bg_count = 0;
-
     #[cfg(unix)]
     {
-        // Use ps with custom format to get the pid and full command line
-        let output = Command::new("ps").args(["-eo", "pid,command"]).output();
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines().skip(1) {
-                // Skip header
-                let line = line.trim();
-                if line.is_empty() {
-                    continue;
-                }
-
-                // The first part is PID, the rest is the command string
-                if let Some((pid_str, command)) = line.split_once(' ') {
-                    let command = command.trim();
-
-                    // Get the executable path/name (first word of the command string before args)
-                    let executable = command.split_whitespace().next().unwrap_or("");
-
-                    // Check if the executable is exactly "codex" or ends with "/codex"
-                    let is_codex = executable == "codex" || executable.ends_with("/codex");
-
-                    // Exclude if it's running from an extension or IDE integration (like Antigravity)
-                    // These are expected background processes we shouldn't block on
-                    let is_ide_plugin = command.contains(".antigravity")
-                        || command.contains("openai.chatgpt")
-                        || command.contains(".vscode");
-
-                    // Skip our own app
-                    let is_switcher =
-                        command.contains("codex-switcher") || command.contains("Codex Switcher");
-
-                    if is_codex && !is_switcher {
-                        if let Ok(pid) = pid_str.trim().parse::<u32>() {
-                            if pid != std::process::id() && !pids.contains(&pid) {
-                                if is_ide_plugin {
-                                    bg_count += 1;
-                                } else {
-                                    pids.push(pid);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        let codex_home = get_codex_home()?;
+        let found =
+            process::find_codex_processes(process::default_scanner().as_ref(), &codex_home)?;
+        Ok((found.active, found.background.len()))
     }
 
     #[cfg(windows)]
     {
+        let mut pids = Vec::new();
+
         // Use tasklist on Windows - match exact "codex.exe"
         let output = Command::new("tasklist")
             // Prevent a console window from flashing when this command is invoked from the GUI app.
@@ -121,7 +82,7 @@ fn find_codex_processes() -> anyhow::Result<(Vec<u32>, usize)> {
                 }
             }
         }
-    }
 
-    Ok((pids, bg_count))
+        Ok((pids, 0))
+    }
 }
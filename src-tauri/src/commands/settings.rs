@@ -0,0 +1,23 @@
+//! App settings commands
+
+use crate::settings::{endpoint_config, load_settings, save_settings, validate_settings};
+use crate::types::{AppSettings, EndpointConfig};
+
+/// Get the app-wide settings
+#[tauri::command]
+pub async fn get_app_settings() -> Result<AppSettings, String> {
+    load_settings().map_err(|e| e.to_string())
+}
+
+/// Replace the app-wide settings
+#[tauri::command]
+pub async fn update_app_settings(settings: AppSettings) -> Result<(), String> {
+    validate_settings(&settings).map_err(|e| format!("{e:#}"))?;
+    save_settings(&settings).map_err(|e| e.to_string())
+}
+
+/// Get the effective endpoints after applying settings and environment overrides
+#[tauri::command]
+pub async fn get_endpoint_config() -> Result<EndpointConfig, String> {
+    endpoint_config().map_err(|e| e.to_string())
+}
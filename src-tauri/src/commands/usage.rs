@@ -1,76 +1,119 @@
 //! Usage query Tauri commands
 
-use crate::api::usage::{get_account_usage, refresh_all_usage, warmup_account as send_warmup};
+use crate::api::usage::{
+    get_account_usage_cached, refresh_all_usage, warmup_account as send_warmup, warmup_all,
+};
+use crate::api::usage_summary::summarize_usage;
 use crate::auth::storage::update_last_notifications;
 use crate::auth::{get_account, load_accounts};
-use crate::notifications::check_and_notify;
-use crate::types::{UsageInfo, WarmupSummary};
+use crate::notifications::{
+    check_and_notify, effective_notification_settings, health_check_for_error,
+    notify_if_all_exhausted, record_health, record_usage_health, schedule_reset_notifications,
+};
+use crate::types::{HealthCheck, UsageInfo, UsageSummary, WarmupSummary};
 
-/// Get usage info for a specific account
+/// Get usage info for a specific account.
+/// Served from cache while fresh unless `force_refresh` is set.
 #[tauri::command]
-pub async fn get_usage(account_id: String) -> Result<UsageInfo, String> {
+pub async fn get_usage(
+    app: tauri::AppHandle,
+    account_id: String,
+    force_refresh: Option<bool>,
+) -> Result<UsageInfo, String> {
     let account = get_account(&account_id)
         .map_err(|e| e.to_string())?
         .ok_or_else(|| format!("Account not found: {account_id}"))?;
 
-    get_account_usage(&account).await.map_err(|e| e.to_string())
+    let usage = get_account_usage_cached(&account, force_refresh.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())?;
+    record_usage_health(&app, &account, &usage);
+    Ok(usage)
 }
 
-/// Refresh usage info for all accounts
+/// Refresh usage info for all accounts.
+/// Bypasses the cache unless `force_refresh` is explicitly false.
 #[tauri::command]
 pub async fn refresh_all_accounts_usage(
     app: tauri::AppHandle,
+    force_refresh: Option<bool>,
 ) -> Result<Vec<UsageInfo>, String> {
     let store = load_accounts().map_err(|e| e.to_string())?;
-    let usage_list = refresh_all_usage(&store.accounts).await;
+    let usage_list = refresh_all_usage(&store.accounts, force_refresh.unwrap_or(true)).await;
 
     // Check thresholds and send notifications
     for usage in &usage_list {
         if let Some(account) = store.accounts.iter().find(|a| a.id == usage.account_id) {
             let mut last = account.last_notifications.clone();
-            if let Err(e) = check_and_notify(
+            check_and_notify(
                 &app,
                 &account.name,
                 usage,
-                &account.notification_settings,
+                &effective_notification_settings(account),
                 &mut last,
-            ) {
-                eprintln!("[Notifications] Failed to send notification for {}: {}", account.name, e);
-            }
+            );
             // Update last_notifications in storage if changed
             if last != account.last_notifications {
                 if let Err(e) = update_last_notifications(&account.id, &last) {
                     eprintln!("[Notifications] Failed to update last_notifications for {}: {}", account.id, e);
                 }
             }
+            schedule_reset_notifications(&app, account, usage);
+            record_usage_health(&app, account, usage);
         }
     }
+    notify_if_all_exhausted(&app, &store.accounts, &usage_list);
 
     Ok(usage_list)
 }
 
+/// Aggregate the latest usage across all accounts.
+/// Uses cached usage while fresh unless `force_refresh` is set.
+#[tauri::command]
+pub async fn get_usage_summary(force_refresh: Option<bool>) -> Result<UsageSummary, String> {
+    let store = load_accounts().map_err(|e| e.to_string())?;
+    let usage_list = refresh_all_usage(&store.accounts, force_refresh.unwrap_or(false)).await;
+    Ok(summarize_usage(&store.accounts, &usage_list))
+}
+
 /// Send a minimal warm-up request for one account
 #[tauri::command]
-pub async fn warmup_account(account_id: String) -> Result<(), String> {
+pub async fn warmup_account(app: tauri::AppHandle, account_id: String) -> Result<(), String> {
     let account = get_account(&account_id)
         .map_err(|e| e.to_string())?
         .ok_or_else(|| format!("Account not found: {account_id}"))?;
 
-    send_warmup(&account).await.map_err(|e| e.to_string())
+    match send_warmup(&account).await {
+        Ok(()) => {
+            record_health(&app, &account, HealthCheck::Warmup, Ok(()));
+            Ok(())
+        }
+        Err(e) => {
+            let check = health_check_for_error(&e, HealthCheck::Warmup);
+            record_health(&app, &account, check, Err(format!("{e:#}")));
+            Err(e.to_string())
+        }
+    }
 }
 
 /// Send minimal warm-up requests for all accounts
 #[tauri::command]
-pub async fn warmup_all_accounts() -> Result<WarmupSummary, String> {
+pub async fn warmup_all_accounts(app: tauri::AppHandle) -> Result<WarmupSummary, String> {
     let store = load_accounts().map_err(|e| e.to_string())?;
     let total_accounts = store.accounts.len();
-    let mut failed_account_ids = Vec::new();
+    let failures = warmup_all(&store.accounts).await;
 
     for account in &store.accounts {
-        if send_warmup(account).await.is_err() {
-            failed_account_ids.push(account.id.clone());
-        }
+        let (check, result) = match failures.iter().find(|(id, _)| *id == account.id) {
+            Some((_, e)) => (
+                health_check_for_error(e, HealthCheck::Warmup),
+                Err(format!("{e:#}")),
+            ),
+            None => (HealthCheck::Warmup, Ok(())),
+        };
+        record_health(&app, account, check, result);
     }
+    let failed_account_ids: Vec<String> = failures.into_iter().map(|(id, _)| id).collect();
 
     let warmed_accounts = total_accounts.saturating_sub(failed_account_ids.len());
     Ok(WarmupSummary {
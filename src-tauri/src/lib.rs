@@ -2,17 +2,28 @@
 
 pub mod api;
 pub mod auth;
+pub mod cli;
 pub mod commands;
 pub mod notifications;
+pub mod process;
+pub mod settings;
 pub mod types;
 
+#[cfg(test)]
+mod test_support;
+
 use commands::{
-    add_account_from_file, cancel_login, check_codex_processes, complete_login, delete_account,
-    export_accounts_full_encrypted_file, export_accounts_slim_text, get_active_account_info,
-    get_notification_settings, get_usage, import_accounts_full_encrypted_file,
-    import_accounts_slim_text, list_accounts, refresh_all_accounts_usage, rename_account,
-    reset_notification_history, start_login, switch_account, update_notification_settings,
-    warmup_account, warmup_all_accounts,
+    add_account_from_file, add_workspace_account, apply_notification_settings_to_all, cancel_login,
+    check_codex_processes, complete_login, complete_manual_login, delete_account,
+    export_accounts_full_encrypted_file, export_accounts_slim_text, get_account_health,
+    get_active_account_info, get_app_settings, get_endpoint_config, get_notification_overrides,
+    get_notification_settings, get_usage, get_usage_summary, import_accounts_full_encrypted_file,
+    import_accounts_slim_text, list_account_workspaces, list_accounts, list_notification_history,
+    list_pending_logins, logout_account, refresh_all_accounts_usage, rename_account,
+    reset_notification_history, restart_ide_app_servers, send_test_notification,
+    start_device_login, start_login, start_manual_login, switch_account, update_app_settings,
+    update_notification_overrides, update_notification_settings, warmup_account,
+    warmup_all_accounts,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -21,14 +32,23 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            // Deliver digests queued before the last shutdown
+            notifications::restore_digest_queue(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Account management
             list_accounts,
             get_active_account_info,
             add_account_from_file,
             switch_account,
+            restart_ide_app_servers,
             delete_account,
+            logout_account,
             rename_account,
+            list_account_workspaces,
+            add_workspace_account,
             export_accounts_slim_text,
             import_accounts_slim_text,
             export_accounts_full_encrypted_file,
@@ -36,9 +56,14 @@ pub fn run() {
             // OAuth
             start_login,
             complete_login,
+            start_device_login,
+            start_manual_login,
+            complete_manual_login,
+            list_pending_logins,
             cancel_login,
             // Usage
             get_usage,
+            get_usage_summary,
             refresh_all_accounts_usage,
             warmup_account,
             warmup_all_accounts,
@@ -47,7 +72,17 @@ pub fn run() {
             // Notifications
             update_notification_settings,
             get_notification_settings,
+            get_notification_overrides,
+            update_notification_overrides,
+            apply_notification_settings_to_all,
             reset_notification_history,
+            send_test_notification,
+            list_notification_history,
+            get_account_health,
+            // Settings
+            get_app_settings,
+            update_app_settings,
+            get_endpoint_config,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -2,5 +2,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    if let Some(code) = codex_switcher_lib::cli::run_from_args() {
+        std::process::exit(code);
+    }
     codex_switcher_lib::run()
 }
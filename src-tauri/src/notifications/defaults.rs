@@ -0,0 +1,77 @@
+//! Global notification defaults and per-account inheritance
+
+use anyhow::Result;
+
+use super::{validate_quiet_hours, validate_sink_config};
+use crate::settings::load_settings;
+use crate::types::{GlobalNotificationSettings, NotificationSettings, StoredAccount};
+
+/// Global notification settings, falling back to defaults if settings.json is unreadable
+pub fn global_notification_settings() -> GlobalNotificationSettings {
+    match load_settings() {
+        Ok(settings) => settings.notifications,
+        Err(e) => {
+            println!("[Notifications] Failed to load settings, using default delivery: {e}");
+            GlobalNotificationSettings::default()
+        }
+    }
+}
+
+/// Settings of an account after applying its overrides to the global defaults
+pub fn effective_notification_settings(account: &StoredAccount) -> NotificationSettings {
+    account
+        .notification_settings
+        .resolve(&global_notification_settings().defaults)
+}
+
+/// Validate notification settings
+pub fn validate_notification_settings(settings: &NotificationSettings) -> Result<()> {
+    if let Some(threshold) = settings.primary_threshold {
+        if threshold > 100 {
+            anyhow::bail!("primary_threshold must be between 0 and 100");
+        }
+    }
+    if let Some(threshold) = settings.secondary_threshold {
+        if threshold > 100 {
+            anyhow::bail!("secondary_threshold must be between 0 and 100");
+        }
+    }
+    if let Some(threshold) = settings.credits_threshold {
+        if threshold > 100 {
+            anyhow::bail!("credits_threshold must be between 0 and 100");
+        }
+    }
+    if let Some(threshold) = settings.credits_balance_threshold {
+        if !threshold.is_finite() {
+            anyhow::bail!("credits_balance_threshold must be a number");
+        }
+    }
+    if let Some(budget) = settings.credits_budget {
+        if !budget.is_finite() || budget <= 0.0 {
+            anyhow::bail!("credits_budget must be greater than 0");
+        }
+    }
+    if let Some(currency) = &settings.credits_currency {
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+            anyhow::bail!("credits_currency must be a 3-letter currency code, e.g. USD");
+        }
+    }
+    if settings.escalation_levels.iter().any(|level| *level > 100) {
+        anyhow::bail!("escalation_levels must be between 0 and 100");
+    }
+    if settings.hysteresis_percent > 50 {
+        anyhow::bail!("hysteresis_percent must be between 0 and 50");
+    }
+    for sink in settings.sinks.iter().flatten() {
+        validate_sink_config(sink)?;
+    }
+    if let Some(quiet_hours) = &settings.quiet_hours {
+        validate_quiet_hours(quiet_hours)?;
+    }
+    if settings.min_interval_minutes < 1 {
+        anyhow::bail!("min_interval_minutes must be at least 1");
+    }
+    // Note: min_interval_minutes is u8, max value is 255, which is ~4.25 hours
+    // This is a reasonable maximum for a notification cooldown
+    Ok(())
+}
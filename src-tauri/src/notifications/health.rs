@@ -0,0 +1,328 @@
+//! Account health: alert when token refresh, usage fetch or warm-up keeps
+//! failing for an account, and clear the alert once it recovers.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use tauri::AppHandle;
+
+use super::{effective_notification_settings, notify, NotificationMessage};
+use crate::auth::TokenRefreshRejected;
+use crate::types::{
+    AccountHealthIssue, AuthData, HealthCheck, NotificationSettings, StoredAccount, UsageErrorKind,
+    UsageFreshness, UsageInfo,
+};
+
+/// Failing checks keyed by (account ID, check)
+static ISSUES: Mutex<Option<HashMap<(String, HealthCheck), AccountHealthIssue>>> = Mutex::new(None);
+
+impl HealthCheck {
+    fn label(self) -> &'static str {
+        match self {
+            HealthCheck::TokenRefresh => "Token refresh",
+            HealthCheck::UsageFetch => "Usage fetch",
+            HealthCheck::Warmup => "Warm-up",
+        }
+    }
+}
+
+/// The check an error belongs to: refresh tokens the issuer rejected count as
+/// token refresh failures (a new login is needed), anything else as `fallback`
+pub fn health_check_for_error(err: &anyhow::Error, fallback: HealthCheck) -> HealthCheck {
+    let rejected = err.chain().any(|cause| {
+        cause
+            .downcast_ref::<TokenRefreshRejected>()
+            .is_some_and(|r| (400..500).contains(&r.status) && r.status != 429)
+    });
+    if rejected {
+        HealthCheck::TokenRefresh
+    } else {
+        fallback
+    }
+}
+
+/// Current failing checks, optionally for one account only
+pub fn list_health_issues(account_id: Option<&str>) -> Vec<AccountHealthIssue> {
+    let issues = ISSUES.lock().unwrap();
+    let mut list: Vec<AccountHealthIssue> = issues
+        .iter()
+        .flat_map(|issues| issues.values())
+        .filter(|issue| account_id.is_none_or(|id| issue.account_id == id))
+        .cloned()
+        .collect();
+    list.sort_by_key(|issue| issue.first_failed_at);
+    list
+}
+
+/// Forget the health state of an account (e.g. after it was deleted)
+pub fn clear_health_issues(account_id: &str) {
+    if let Some(issues) = ISSUES.lock().unwrap().as_mut() {
+        issues.retain(|(id, _), _| id != account_id);
+    }
+}
+
+/// Record the outcome of a check. Alerts once the failure threshold is
+/// reached (then at most once per cooldown) and announces the recovery of an
+/// alerted check.
+pub fn record_health(
+    app: &AppHandle,
+    account: &StoredAccount,
+    check: HealthCheck,
+    result: Result<(), String>,
+) {
+    let settings = effective_notification_settings(account);
+    let outcome = {
+        let mut guard = ISSUES.lock().unwrap();
+        let issues = guard.get_or_insert_with(HashMap::new);
+        update_issues(issues, account, &settings, check, result, Utc::now())
+    };
+
+    let Some((message, label)) = outcome else {
+        return;
+    };
+    if !settings.enabled {
+        return;
+    }
+
+    let vars = [("check", label)];
+    notify(app, &settings, message.with_template(&settings, &vars));
+}
+
+/// Apply one check outcome to the issues; returns the alert or recovery
+/// message to send, with the label of the check(s) it is about
+fn update_issues(
+    issues: &mut HashMap<(String, HealthCheck), AccountHealthIssue>,
+    account: &StoredAccount,
+    settings: &NotificationSettings,
+    check: HealthCheck,
+    result: Result<(), String>,
+    now: DateTime<Utc>,
+) -> Option<(NotificationMessage, String)> {
+    let error = match result {
+        Ok(()) => {
+            // Any authenticated call that worked also proves the tokens are good
+            let mut checks = vec![check];
+            if check != HealthCheck::TokenRefresh
+                && matches!(account.auth_data, AuthData::ChatGPT { .. })
+            {
+                checks.push(HealthCheck::TokenRefresh);
+            }
+
+            let recovered: Vec<&str> = checks
+                .into_iter()
+                .filter(|check| {
+                    issues
+                        .remove(&(account.id.clone(), *check))
+                        .is_some_and(|issue| issue.alerted_at.is_some())
+                })
+                .map(HealthCheck::label)
+                .collect();
+            if recovered.is_empty() {
+                return None;
+            }
+
+            let label = recovered.join(" and ");
+            println!("[Health] {label} recovered for account {}", account.name);
+            let verb = if recovered.len() > 1 { "are" } else { "is" };
+            let message = NotificationMessage::new(
+                &account.name,
+                "health_recovered",
+                format!("{label} {verb} working again"),
+            )
+            .for_account(&account.id);
+            return Some((message, label));
+        }
+        Err(error) => error,
+    };
+
+    let issue = issues
+        .entry((account.id.clone(), check))
+        .or_insert_with(|| AccountHealthIssue {
+            account_id: account.id.clone(),
+            check,
+            consecutive_failures: 0,
+            last_error: String::new(),
+            first_failed_at: now,
+            alerted_at: None,
+        });
+    issue.consecutive_failures += 1;
+    issue.last_error = error;
+
+    let threshold_reached = settings
+        .health_failure_threshold
+        .is_some_and(|threshold| issue.consecutive_failures >= threshold.max(1) as u32);
+    let cooldown = Duration::minutes(settings.health_cooldown_minutes as i64);
+    let cooled_down = issue.alerted_at.is_none_or(|at| now - at >= cooldown);
+    if !(settings.enabled && threshold_reached && cooled_down) {
+        return None;
+    }
+
+    issue.alerted_at = Some(now);
+    let message = if check == HealthCheck::TokenRefresh {
+        // The refresh token was rejected: critical, may bypass quiet hours
+        NotificationMessage::new(
+            &account.name,
+            "login_required",
+            "Sign-in expired or was revoked - log in again to keep using this account".to_string(),
+        )
+        .critical()
+    } else {
+        NotificationMessage::new(
+            &account.name,
+            "health_failing",
+            format!(
+                "{} failed {} times in a row: {}",
+                check.label(),
+                issue.consecutive_failures,
+                issue.last_error
+            ),
+        )
+    };
+    let message = message
+        .for_account(&account.id)
+        .with_value(issue.consecutive_failures as f64);
+    Some((message, check.label().to_string()))
+}
+
+/// Record the token refresh and usage fetch health from a usage result
+pub fn record_usage_health(app: &AppHandle, account: &StoredAccount, usage: &UsageInfo) {
+    // Served from cache: nothing was fetched
+    if usage.freshness == UsageFreshness::Cached {
+        return;
+    }
+
+    match &usage.error_detail {
+        // Also clears a token refresh issue of ChatGPT accounts
+        None if usage.error.is_none() => {
+            record_health(app, account, HealthCheck::UsageFetch, Ok(()));
+        }
+        Some(detail) if detail.kind == UsageErrorKind::Unsupported => {}
+        Some(detail) if detail.kind == UsageErrorKind::LoginRequired => {
+            let error = Err(detail.message.clone());
+            record_health(app, account, HealthCheck::TokenRefresh, error);
+        }
+        // ChatGPT accounts only get here after a successful token refresh
+        Some(detail) if detail.kind == UsageErrorKind::Unauthorized => {
+            if matches!(account.auth_data, AuthData::ChatGPT { .. }) {
+                record_health(app, account, HealthCheck::TokenRefresh, Ok(()));
+            }
+            let error = Err(detail.message.clone());
+            record_health(app, account, HealthCheck::UsageFetch, error);
+        }
+        _ => {
+            let error = usage
+                .error
+                .clone()
+                .unwrap_or_else(|| "Unknown error".to_string());
+            record_health(app, account, HealthCheck::UsageFetch, Err(error));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chatgpt_account() -> StoredAccount {
+        StoredAccount::new_chatgpt(
+            "Work".to_string(),
+            None,
+            None,
+            "id".to_string(),
+            "access".to_string(),
+            "refresh".to_string(),
+            None,
+        )
+    }
+
+    fn settings() -> NotificationSettings {
+        NotificationSettings {
+            enabled: true,
+            health_failure_threshold: Some(1),
+            ..NotificationSettings::default()
+        }
+    }
+
+    fn fail(
+        issues: &mut HashMap<(String, HealthCheck), AccountHealthIssue>,
+        account: &StoredAccount,
+        check: HealthCheck,
+    ) -> Option<(NotificationMessage, String)> {
+        let error = Err("rejected".to_string());
+        update_issues(issues, account, &settings(), check, error, Utc::now())
+    }
+
+    #[test]
+    fn rejected_refresh_alerts_login_required() {
+        let account = chatgpt_account();
+        let mut issues = HashMap::new();
+
+        let (message, label) = fail(&mut issues, &account, HealthCheck::TokenRefresh).unwrap();
+        assert_eq!(message.event, "login_required");
+        assert!(message.critical);
+        assert_eq!(message.account_id.as_deref(), Some(account.id.as_str()));
+        assert_eq!(label, "Token refresh");
+    }
+
+    #[test]
+    fn successful_warmup_clears_token_refresh_issue() {
+        let account = chatgpt_account();
+        let mut issues = HashMap::new();
+        fail(&mut issues, &account, HealthCheck::TokenRefresh);
+
+        let (message, _) = update_issues(
+            &mut issues,
+            &account,
+            &settings(),
+            HealthCheck::Warmup,
+            Ok(()),
+            Utc::now(),
+        )
+        .unwrap();
+
+        assert!(issues.is_empty());
+        assert_eq!(message.event, "health_recovered");
+        assert_eq!(message.body, "Token refresh is working again");
+    }
+
+    #[test]
+    fn api_key_success_leaves_other_checks_alone() {
+        let account = StoredAccount::new_api_key("Key".to_string(), "sk".to_string());
+        let mut issues = HashMap::new();
+        fail(&mut issues, &account, HealthCheck::UsageFetch);
+
+        let outcome = update_issues(
+            &mut issues,
+            &account,
+            &settings(),
+            HealthCheck::Warmup,
+            Ok(()),
+            Utc::now(),
+        );
+
+        assert!(outcome.is_none());
+        assert!(issues.contains_key(&(account.id.clone(), HealthCheck::UsageFetch)));
+    }
+
+    #[test]
+    fn only_rejected_refresh_tokens_count_as_token_refresh() {
+        let rejected = |status| {
+            anyhow::Error::new(TokenRefreshRejected {
+                status,
+                body: String::new(),
+            })
+            .context("Failed to refresh tokens")
+        };
+
+        let check = |err| health_check_for_error(&err, HealthCheck::Warmup);
+        assert_eq!(check(rejected(401)), HealthCheck::TokenRefresh);
+        assert_eq!(check(rejected(400)), HealthCheck::TokenRefresh);
+        assert_eq!(check(rejected(429)), HealthCheck::Warmup);
+        assert_eq!(check(rejected(503)), HealthCheck::Warmup);
+        assert_eq!(
+            check(anyhow::anyhow!("ChatGPT warm-up failed with status 401")),
+            HealthCheck::Warmup
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Notification history stored in ~/.codex-switcher/notification_history.json
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+
+use super::NotificationMessage;
+use crate::auth::storage::get_config_dir;
+use crate::settings::load_settings;
+use crate::types::{
+    GlobalNotificationSettings, NotificationHistoryEntry, NotificationOutcome, SinkDeliveryResult,
+    StoredAccount,
+};
+
+/// Serializes read-modify-write cycles on the history file
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// Get the path to notification_history.json
+pub fn get_notification_history_file() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("notification_history.json"))
+}
+
+fn load_history_file() -> Result<Vec<NotificationHistoryEntry>> {
+    let path = get_notification_history_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read notification history: {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse notification history: {}", path.display()))
+}
+
+fn save_history_file(entries: &[NotificationHistoryEntry]) -> Result<()> {
+    let path = get_notification_history_file()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
+    }
+
+    let content = serde_json::to_string_pretty(entries)
+        .context("Failed to serialize notification history")?;
+    fs::write(&path, content)
+        .with_context(|| format!("Failed to write notification history: {}", path.display()))
+}
+
+/// Drop entries older than the retention period and beyond the entry limit
+fn apply_retention(
+    entries: &mut Vec<NotificationHistoryEntry>,
+    settings: &GlobalNotificationSettings,
+) {
+    let cutoff = Utc::now() - Duration::days(settings.history_retention_days as i64);
+    entries.retain(|entry| entry.timestamp >= cutoff);
+
+    let excess = entries.len().saturating_sub(settings.history_max_entries);
+    entries.drain(..excess);
+}
+
+/// Append a notification to the history
+pub fn record_notification(message: &NotificationMessage, deliveries: Vec<SinkDeliveryResult>) {
+    let outcome = if deliveries.is_empty() {
+        NotificationOutcome::Queued
+    } else if deliveries.iter().all(|d| d.success) {
+        NotificationOutcome::Delivered
+    } else if deliveries.iter().any(|d| d.success) {
+        NotificationOutcome::PartiallyDelivered
+    } else {
+        NotificationOutcome::Failed
+    };
+
+    let entry = NotificationHistoryEntry {
+        timestamp: Utc::now(),
+        account_id: message.account_id.clone(),
+        account_name: message.account_name.clone(),
+        event: message.event.clone(),
+        title: message.title.clone(),
+        body: message.body.clone(),
+        value: message.value,
+        critical: message.critical,
+        outcome,
+        deliveries,
+    };
+
+    let settings = load_settings().map(|s| s.notifications).unwrap_or_default();
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let result = load_history_file().and_then(|mut entries| {
+        entries.push(entry);
+        apply_retention(&mut entries, &settings);
+        save_history_file(&entries)
+    });
+    if let Err(e) = result {
+        println!("[Notifications] Failed to record notification history: {e:#}");
+    }
+}
+
+/// Recorded notifications, newest first, optionally for one account only
+pub fn load_notification_history(
+    account: Option<&StoredAccount>,
+    limit: Option<usize>,
+) -> Result<Vec<NotificationHistoryEntry>> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let entries = load_history_file()?;
+
+    Ok(entries
+        .into_iter()
+        .rev()
+        .filter(|entry| account.is_none_or(|account| is_for_account(entry, account)))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+/// Entries are matched by account ID; older entries without one by name
+fn is_for_account(entry: &NotificationHistoryEntry, account: &StoredAccount) -> bool {
+    match &entry.account_id {
+        Some(account_id) => account_id == &account.id,
+        None => entry.account_name == account.name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(account_id: Option<&str>, account_name: &str) -> NotificationHistoryEntry {
+        NotificationHistoryEntry {
+            timestamp: Utc::now(),
+            account_id: account_id.map(String::from),
+            account_name: account_name.to_string(),
+            event: "usage_level".to_string(),
+            title: String::new(),
+            body: String::new(),
+            value: None,
+            critical: false,
+            outcome: NotificationOutcome::Delivered,
+            deliveries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_by_id_across_renames() {
+        let mut account = StoredAccount::new_api_key("Renamed".to_string(), "sk".to_string());
+        account.id = "acc-1".to_string();
+
+        assert!(is_for_account(&entry(Some("acc-1"), "Old name"), &account));
+        assert!(!is_for_account(&entry(Some("acc-2"), "Renamed"), &account));
+    }
+
+    #[test]
+    fn legacy_entries_match_by_name() {
+        let account = StoredAccount::new_api_key("Work".to_string(), "sk".to_string());
+        let legacy: NotificationHistoryEntry = serde_json::from_value(serde_json::json!({
+            "timestamp": "2026-10-01T12:00:00Z",
+            "account_name": "Work",
+            "event": "credits_low",
+            "title": "t",
+            "body": "b",
+            "value": 3.5,
+            "critical": false,
+            "outcome": "delivered",
+            "deliveries": []
+        }))
+        .unwrap();
+
+        assert_eq!(legacy.account_id, None);
+        assert!(is_for_account(&legacy, &account));
+        assert!(!is_for_account(&entry(None, "Personal"), &account));
+    }
+}
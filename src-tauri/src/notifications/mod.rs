@@ -1,10 +1,32 @@
 //! OS native notifications for usage thresholds
 
+mod defaults;
+mod health;
+mod history;
+mod quiet_hours;
+mod reset;
+mod sink;
+
+pub use defaults::*;
+pub use health::*;
+pub use history::*;
+pub use quiet_hours::*;
+pub use reset::*;
+pub use sink::*;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use tauri::AppHandle;
-use tauri_plugin_notification::NotificationExt;
 
-use crate::types::{LastNotifications, NotificationSettings, UsageInfo};
-use chrono::{DateTime, Duration, Utc};
+use crate::api::credits::{format_credits_amount, parse_credits_balance, CreditsBalance};
+use crate::api::usage_summary::summarize_usage;
+use crate::types::{
+    LastNotifications, NotificationSettings, StoredAccount, UsageInfo, WindowLevels,
+};
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+
+/// Set while the "all accounts exhausted" notification is outstanding
+static ALL_EXHAUSTED_NOTIFIED: AtomicBool = AtomicBool::new(false);
 
 /// Get the icon path for notifications
 /// On Linux/KDE, we need an absolute path or a themed icon name
@@ -35,86 +57,207 @@ fn get_notification_icon_path() -> String {
     }
 }
 
-/// Check if notification should be sent based on threshold and cooldown
-fn should_notify(
+/// Check if the cooldown since the last notification has elapsed
+fn cooldown_elapsed(last_notified: Option<DateTime<Utc>>, min_interval: i64) -> bool {
+    match last_notified {
+        Some(last) => Utc::now().signed_duration_since(last) >= Duration::minutes(min_interval),
+        None => true,
+    }
+}
+
+/// Reset times closer than this belong to the same window period
+const RESET_JITTER_SECS: i64 = 300;
+
+/// Pick the escalation level to notify for a rate limit window, if any.
+///
+/// Each level fires once per window period; a new `resets_at` starts a new period.
+/// A fired level is re-armed only after usage drops `hysteresis` points below it.
+fn next_escalation_level(
     current_value: f64,
-    threshold: Option<u8>,
-    last_notified: Option<DateTime<Utc>>,
-    min_interval: i64,
-) -> bool {
-    let Some(threshold) = threshold else {
-        return false;
+    resets_at: Option<i64>,
+    levels: &[u8],
+    hysteresis: u8,
+    state: &mut WindowLevels,
+) -> Option<u8> {
+    // Reset times derived from a relative delay jitter by a few seconds between polls
+    let new_period = match (resets_at, state.resets_at) {
+        (Some(new), Some(old)) => (new - old).abs() > RESET_JITTER_SECS,
+        (Some(_), None) => true,
+        (None, _) => false,
     };
-    if current_value < threshold as f64 {
-        return false;
+    if new_period {
+        state.resets_at = resets_at;
+        state.fired.clear();
     }
-    if let Some(last) = last_notified {
-        let elapsed = Utc::now().signed_duration_since(last);
-        if elapsed < Duration::minutes(min_interval) {
-            return false;
+
+    state
+        .fired
+        .retain(|level| current_value >= *level as f64 - hysteresis as f64);
+
+    let reached: Vec<u8> = levels
+        .iter()
+        .copied()
+        .filter(|level| current_value >= *level as f64)
+        .collect();
+    let highest = *reached.last()?;
+    if state.fired.contains(&highest) {
+        return None;
+    }
+
+    // Levels passed in one jump are covered by the highest one
+    for level in reached {
+        if !state.fired.contains(&level) {
+            state.fired.push(level);
         }
     }
-    true
+    Some(highest)
 }
 
-/// Parse credits balance string like "$10.50" to get the numeric value
-fn parse_credits_balance(balance: &str) -> Option<f64> {
-    // Remove currency symbol and parse
-    let numeric: String = balance.chars().filter(|c| c.is_numeric() || *c == '.').collect();
-    numeric.parse::<f64>().ok()
-}
+/// Describe why a credits balance crossed the account's thresholds, if it did
+fn credits_alert(balance: &CreditsBalance, settings: &NotificationSettings) -> Option<String> {
+    let currency = balance.currency.as_deref().or(settings.credits_currency.as_deref());
+    let formatted = format_credits_amount(balance.amount, currency);
 
-/// Get estimated maximum credits based on plan type.
-/// 
-/// Note: These are rough estimates based on typical OpenAI plan limits.
-/// Actual limits may vary and change over time. Consider making these
-/// configurable in a future release.
-fn get_plan_credits_max(plan_type: Option<&str>) -> f64 {
-    match plan_type {
-        Some("free") => 0.0,     // Free tier typically has no credits
-        Some("plus") => 50.0,    // Plus typically has ~$50 in credits
-        Some("pro") => 100.0,    // Pro has higher limits
-        Some("team") => 500.0,   // Team has pooled credits
-        Some("business") => 1000.0,
-        Some("enterprise") => 5000.0,
-        _ => 100.0, // Default estimate for unknown plans
+    // Amounts in another currency than the configured one cannot be compared
+    if let (Some(expected), Some(actual)) = (&settings.credits_currency, &balance.currency) {
+        if expected != actual {
+            println!(
+                "[Notifications] Credits balance is in {}, thresholds are in {}",
+                actual, expected
+            );
+            return None;
+        }
     }
+
+    if let Some(threshold) = settings.credits_balance_threshold {
+        if balance.amount <= threshold {
+            return Some(format!(
+                "{} (threshold {})",
+                formatted,
+                format_credits_amount(threshold, currency)
+            ));
+        }
+    }
+
+    if let (Some(threshold), Some(budget)) = (settings.credits_threshold, settings.credits_budget) {
+        let used_percent = (budget - balance.amount) / budget * 100.0;
+        if budget > 0.0 && used_percent >= threshold as f64 {
+            return Some(format!(
+                "{} left, {:.0}% of the {} budget used",
+                formatted,
+                used_percent,
+                format_credits_amount(budget, currency)
+            ));
+        }
+    }
+
+    None
 }
 
-/// Send OS notification for usage threshold
+/// Send a notification for a usage escalation level
 pub fn send_usage_notification(
     app: &AppHandle,
+    settings: &NotificationSettings,
+    account_id: &str,
     account_name: &str,
     usage_type: &str,
     current_percent: f64,
-) -> Result<(), String> {
-    let icon_path = get_notification_icon_path();
-    app.notification()
-        .builder()
-        .title(format!("Codex Switcher: {}", account_name))
-        .body(format!(
-            "{} usage at {:.1}% - threshold exceeded",
-            usage_type, current_percent
-        ))
-        .icon(&icon_path)
-        .show()
-        .map_err(|e| e.to_string())
+    level: u8,
+) {
+    let body = if level >= 100 {
+        format!("{} usage at {:.1}% - limit reached", usage_type, current_percent)
+    } else {
+        format!(
+            "{} usage at {:.1}% - {}% level reached",
+            usage_type, current_percent, level
+        )
+    };
+    let vars = [
+        ("usage_type", usage_type.to_string()),
+        ("percent", format!("{:.1}", current_percent)),
+        ("level", level.to_string()),
+    ];
+    let message = NotificationMessage::new(account_name, "usage_level", body)
+        .for_account(account_id)
+        .with_value(current_percent)
+        .with_template(settings, &vars);
+    notify(app, settings, message);
 }
 
-/// Send OS notification for low credits
+/// Send a notification for low credits
 pub fn send_credits_notification(
     app: &AppHandle,
+    settings: &NotificationSettings,
+    account_id: &str,
     account_name: &str,
-    balance: &str,
-) -> Result<(), String> {
-    let icon_path = get_notification_icon_path();
-    app.notification()
-        .builder()
-        .title(format!("Codex Switcher: {}", account_name))
-        .body(format!("Credits balance is low: {}", balance))
-        .icon(&icon_path)
-        .show()
-        .map_err(|e| e.to_string())
+    reason: &str,
+    balance: f64,
+) {
+    let body = format!("Credits balance is low: {}", reason);
+    let vars = [
+        ("reason", reason.to_string()),
+        ("balance", format!("{:.2}", balance)),
+    ];
+    let message = NotificationMessage::new(account_name, "credits_low", body)
+        .for_account(account_id)
+        .with_value(balance)
+        .with_template(settings, &vars);
+    notify(app, settings, message);
+}
+
+/// Send a notification when an exhausted window has reset
+pub fn send_reset_notification(
+    app: &AppHandle,
+    settings: &NotificationSettings,
+    account_id: &str,
+    account_name: &str,
+    usage_type: &str,
+) {
+    let body = format!("{} has reset - capacity is available again", usage_type);
+    let vars = [("usage_type", usage_type.to_string())];
+    let message = NotificationMessage::new(account_name, "window_reset", body)
+        .for_account(account_id)
+        .with_template(settings, &vars);
+    notify(app, settings, message);
+}
+
+/// Send a critical notification once every account with usage data is exhausted.
+/// Re-armed as soon as any account has capacity again.
+pub fn notify_if_all_exhausted(app: &AppHandle, accounts: &[StoredAccount], usage: &[UsageInfo]) {
+    let defaults = global_notification_settings().defaults;
+    if !accounts.iter().any(|a| a.notification_settings.resolve(&defaults).enabled) {
+        return;
+    }
+
+    let summary = summarize_usage(accounts, usage);
+    let all_exhausted = summary.accounts_with_data > 0 && summary.exhausted_accounts == summary.accounts_with_data;
+    if !all_exhausted {
+        ALL_EXHAUSTED_NOTIFIED.store(false, Ordering::SeqCst);
+        return;
+    }
+    if ALL_EXHAUSTED_NOTIFIED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let next_reset = summary
+        .next_reset_at
+        .and_then(|ts| Local.timestamp_opt(ts, 0).single())
+        .map(|t| format!(" Next reset at {}.", t.format("%H:%M")))
+        .unwrap_or_default();
+    let body = format!("All accounts have exhausted their rate limits.{}", next_reset);
+    let vars = [("next_reset", next_reset.trim().to_string())];
+    let message = NotificationMessage::new("all accounts", "all_exhausted", body)
+        .critical()
+        .with_template(&defaults, &vars);
+
+    // The global defaults apply; re-arm if no sink accepted it
+    let app = app.clone();
+    tokio::spawn(async move {
+        if let Err(e) = notify_now(&app, &defaults, message).await {
+            eprintln!("[Notifications] Failed to send all-exhausted notification: {}", e);
+            ALL_EXHAUSTED_NOTIFIED.store(false, Ordering::SeqCst);
+        }
+    });
 }
 
 /// Check usage and send notifications if thresholds exceeded
@@ -124,63 +267,128 @@ pub fn check_and_notify(
     usage: &UsageInfo,
     settings: &NotificationSettings,
     last: &mut LastNotifications,
-) -> Result<(), String> {
+) {
     if !settings.enabled {
-        return Ok(());
+        return;
     }
 
-    // Check primary threshold
+    // Check primary escalation levels
     if let Some(primary) = usage.primary_used_percent {
-        if should_notify(
+        if let Some(level) = next_escalation_level(
             primary,
-            settings.primary_threshold,
-            last.primary,
-            settings.min_interval_minutes as i64,
+            usage.primary_resets_at,
+            &settings.levels_for(settings.primary_threshold),
+            settings.hysteresis_percent,
+            &mut last.primary_levels,
         ) {
-            send_usage_notification(app, account_name, "Primary rate limit", primary)?;
+            send_usage_notification(app, settings, &usage.account_id, account_name, "Primary rate limit", primary, level);
             last.primary = Some(Utc::now());
         }
     }
 
-    // Check secondary threshold
+    // Check secondary escalation levels
     if let Some(secondary) = usage.secondary_used_percent {
-        if should_notify(
+        if let Some(level) = next_escalation_level(
             secondary,
-            settings.secondary_threshold,
-            last.secondary,
-            settings.min_interval_minutes as i64,
+            usage.secondary_resets_at,
+            &settings.levels_for(settings.secondary_threshold),
+            settings.hysteresis_percent,
+            &mut last.secondary_levels,
         ) {
-            send_usage_notification(app, account_name, "Secondary rate limit", secondary)?;
+            send_usage_notification(app, settings, &usage.account_id, account_name, "Secondary rate limit", secondary, level);
             last.secondary = Some(Utc::now());
         }
     }
 
-    // Check credits threshold
-    // Only notify if: has credits, not unlimited, balance is set, and threshold is configured
-    if let (Some(has_credits), Some(unlimited)) = (usage.has_credits, usage.unlimited_credits) {
-        if has_credits && !unlimited {
-            if let Some(ref balance_str) = usage.credits_balance {
-                if let Some(threshold) = settings.credits_threshold {
-                    if let Some(balance) = parse_credits_balance(balance_str) {
-                        let max_credits = get_plan_credits_max(usage.plan_type.as_deref());
-                        if max_credits > 0.0 {
-                            let used_percent = ((max_credits - balance) / max_credits) * 100.0;
-
-                            if should_notify(
-                                used_percent,
-                                Some(threshold),
-                                last.credits,
-                                settings.min_interval_minutes as i64,
-                            ) {
-                                send_credits_notification(app, account_name, balance_str)?;
-                                last.credits = Some(Utc::now());
-                            }
-                        }
-                    }
+    // Check credits thresholds
+    // Only notify if: credits are not unlimited, the balance parses, and a threshold is configured
+    if usage.unlimited_credits != Some(true) {
+        if let Some(balance) = usage.credits_balance.as_deref().and_then(parse_credits_balance) {
+            if let Some(reason) = credits_alert(&balance, settings) {
+                if cooldown_elapsed(last.credits, settings.min_interval_minutes as i64) {
+                    send_credits_notification(app, settings, &usage.account_id, account_name, &reason, balance.amount);
+                    last.credits = Some(Utc::now());
                 }
             }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVELS: [u8; 3] = [75, 90, 100];
+    const RESETS_AT: i64 = 1_900_000_000;
+
+    fn next(value: f64, resets_at: i64, state: &mut WindowLevels) -> Option<u8> {
+        next_escalation_level(value, Some(resets_at), &LEVELS, 5, state)
+    }
+
+    #[test]
+    fn each_level_fires_once_per_period() {
+        let mut state = WindowLevels::default();
+
+        assert_eq!(next(50.0, RESETS_AT, &mut state), None);
+        assert_eq!(next(76.0, RESETS_AT, &mut state), Some(75));
+        assert_eq!(next(80.0, RESETS_AT, &mut state), None);
+        assert_eq!(next(91.0, RESETS_AT, &mut state), Some(90));
+        assert_eq!(next(95.0, RESETS_AT, &mut state), None);
+        assert_eq!(next(100.0, RESETS_AT, &mut state), Some(100));
+        assert_eq!(next(100.0, RESETS_AT, &mut state), None);
+    }
 
-    Ok(())
+    #[test]
+    fn jumping_several_levels_fires_the_highest_once() {
+        let mut state = WindowLevels::default();
+
+        assert_eq!(next(95.0, RESETS_AT, &mut state), Some(90));
+        assert_eq!(state.fired, vec![75, 90]);
+        // The skipped lower level does not fire afterwards
+        assert_eq!(next(80.0, RESETS_AT, &mut state), None);
+    }
+
+    #[test]
+    fn level_rearms_below_the_hysteresis_band() {
+        let mut state = WindowLevels::default();
+        assert_eq!(next(76.0, RESETS_AT, &mut state), Some(75));
+
+        // Still within 5 points of the level: stays fired
+        assert_eq!(next(71.0, RESETS_AT, &mut state), None);
+        assert_eq!(next(76.0, RESETS_AT, &mut state), None);
+
+        // Dropped below 70: fires again on the next crossing
+        assert_eq!(next(69.0, RESETS_AT, &mut state), None);
+        assert!(state.fired.is_empty());
+        assert_eq!(next(76.0, RESETS_AT, &mut state), Some(75));
+    }
+
+    #[test]
+    fn reset_time_jitter_keeps_the_period() {
+        let mut state = WindowLevels::default();
+        assert_eq!(next(91.0, RESETS_AT, &mut state), Some(90));
+
+        let jittered = RESETS_AT + RESET_JITTER_SECS;
+        assert_eq!(next(91.0, jittered, &mut state), None);
+        assert_eq!(state.resets_at, Some(RESETS_AT));
+    }
+
+    #[test]
+    fn new_reset_time_starts_a_new_period() {
+        let mut state = WindowLevels::default();
+        assert_eq!(next(91.0, RESETS_AT, &mut state), Some(90));
+
+        let next_window = RESETS_AT + RESET_JITTER_SECS + 1;
+        assert_eq!(next(91.0, next_window, &mut state), Some(90));
+        assert_eq!(state.resets_at, Some(next_window));
+    }
+
+    #[test]
+    fn missing_reset_time_keeps_the_period() {
+        let mut state = WindowLevels::default();
+        assert_eq!(next(91.0, RESETS_AT, &mut state), Some(90));
+
+        assert_eq!(next_escalation_level(91.0, None, &LEVELS, 5, &mut state), None);
+        assert_eq!(state.resets_at, Some(RESETS_AT));
+    }
 }
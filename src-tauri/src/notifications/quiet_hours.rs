@@ -0,0 +1,380 @@
+//! Quiet hours: notifications raised during a do-not-disturb schedule are
+//! queued in ~/.codex-switcher/notification_queue.json and delivered as a
+//! single digest once it ends, even if the app was restarted in between.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::{deliver_notification, record_notification, NotificationMessage};
+use crate::auth::storage::get_config_dir;
+use crate::types::{NotificationSettings, NotificationSinkConfig, QuietHours};
+
+/// How far ahead to look for the end of quiet hours
+const MAX_LOOKAHEAD_MINUTES: i64 = 8 * 24 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueuedNotification {
+    settings: NotificationSettings,
+    quiet_hours: QuietHours,
+    message: NotificationMessage,
+}
+
+/// Queued notifications, loaded from disk on first use
+static QUEUE: Mutex<Option<Vec<QueuedNotification>>> = Mutex::new(None);
+
+/// Get the path to notification_queue.json
+pub fn get_notification_queue_file() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("notification_queue.json"))
+}
+
+fn load_queue_file() -> Result<Vec<QueuedNotification>> {
+    let path = get_notification_queue_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read notification queue: {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse notification queue: {}", path.display()))
+}
+
+fn save_queue_file(queue: &[QueuedNotification]) -> Result<()> {
+    let path = get_notification_queue_file()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
+    }
+
+    let content =
+        serde_json::to_string_pretty(queue).context("Failed to serialize notification queue")?;
+    fs::write(&path, content)
+        .with_context(|| format!("Failed to write notification queue: {}", path.display()))?;
+
+    // Sink configs may carry webhook headers
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = fs::Permissions::from_mode(0o600);
+        fs::set_permissions(&path, perms)?;
+    }
+
+    Ok(())
+}
+
+/// Run `f` on the queue, then write it back to disk if `f` changed it
+fn with_queue<T>(f: impl FnOnce(&mut Vec<QueuedNotification>) -> (T, bool)) -> T {
+    let mut guard = QUEUE.lock().unwrap();
+    let queue = guard.get_or_insert_with(|| {
+        load_queue_file().unwrap_or_else(|e| {
+            println!("[Notifications] Failed to load notification queue: {e}");
+            Vec::new()
+        })
+    });
+    let (result, changed) = f(queue);
+    if changed {
+        if let Err(e) = save_queue_file(queue) {
+            println!("[Notifications] Failed to save notification queue: {e}");
+        }
+    }
+    result
+}
+
+impl QuietHours {
+    /// Whether quiet hours are in effect at `now`
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let (Ok(start), Ok(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        // An empty schedule; rejected on save, but older settings may hold one
+        if start == end {
+            return false;
+        }
+
+        let local = self.local_time(now);
+        let time = local.time();
+        let today = local.weekday().num_days_from_monday() as u8;
+        let yesterday = (today + 6) % 7;
+        let starts_on = |day: u8| self.weekdays.is_empty() || self.weekdays.contains(&day);
+
+        if start < end {
+            starts_on(today) && time >= start && time < end
+        } else {
+            // Spans midnight
+            (starts_on(today) && time >= start) || (starts_on(yesterday) && time < end)
+        }
+    }
+
+    /// When the current quiet period ends, if one is in effect at `now`
+    pub fn ends_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !self.is_active_at(now) {
+            return None;
+        }
+        let next_minute = now
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(now)
+            + chrono::Duration::minutes(1);
+
+        (0..MAX_LOOKAHEAD_MINUTES)
+            .map(|m| next_minute + chrono::Duration::minutes(m))
+            .find(|t| !self.is_active_at(*t))
+    }
+
+    fn local_time(&self, now: DateTime<Utc>) -> NaiveDateTime {
+        match self
+            .time_zone
+            .as_deref()
+            .and_then(|tz| tz.parse::<Tz>().ok())
+        {
+            Some(tz) => now.with_timezone(&tz).naive_local(),
+            None => now.with_timezone(&Local).naive_local(),
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .with_context(|| format!("'{value}' is not a time in HH:MM format"))
+}
+
+/// Validate a quiet hours schedule before saving it
+pub fn validate_quiet_hours(quiet_hours: &QuietHours) -> Result<()> {
+    let start = parse_time(&quiet_hours.start).context("Invalid quiet hours start")?;
+    let end = parse_time(&quiet_hours.end).context("Invalid quiet hours end")?;
+    if start == end {
+        anyhow::bail!("Quiet hours must end at a different time than they start");
+    }
+    if quiet_hours.weekdays.iter().any(|day| *day > 6) {
+        anyhow::bail!("Quiet hours weekdays must be between 0 (Monday) and 6 (Sunday)");
+    }
+    if let Some(time_zone) = &quiet_hours.time_zone {
+        time_zone
+            .parse::<Tz>()
+            .map_err(|_| anyhow::anyhow!("'{time_zone}' is not a known IANA time zone"))?;
+    }
+    Ok(())
+}
+
+/// Queue a message for the digest sent when quiet hours end
+pub fn queue_for_digest(
+    app: &AppHandle,
+    settings: &NotificationSettings,
+    quiet_hours: &QuietHours,
+    message: NotificationMessage,
+) {
+    println!(
+        "[Notifications] Quiet hours active, queueing {} notification for {}",
+        message.event, message.account_name
+    );
+
+    record_notification(&message, Vec::new());
+    let ends_at = quiet_hours.ends_after(Utc::now());
+    with_queue(|queue| {
+        queue.push(QueuedNotification {
+            settings: settings.clone(),
+            quiet_hours: quiet_hours.clone(),
+            message,
+        });
+        ((), true)
+    });
+
+    // Deliver on time even if nothing else triggers a flush
+    if let Some(ends_at) = ends_at {
+        schedule_flush(app, ends_at);
+    }
+}
+
+fn schedule_flush(app: &AppHandle, at: DateTime<Utc>) {
+    let delay = (at - Utc::now()).to_std().unwrap_or_default() + Duration::from_secs(1);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+        flush_digests(&app).await;
+    });
+}
+
+/// Pick up notifications queued before the app was restarted: deliver those
+/// whose quiet hours ended while it was closed and schedule the rest
+pub fn restore_digest_queue(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        flush_digests(&app).await;
+
+        let now = Utc::now();
+        let ends: BTreeSet<DateTime<Utc>> = with_queue(|queue| {
+            let ends = queue
+                .iter()
+                .filter_map(|queued| queued.quiet_hours.ends_after(now))
+                .collect();
+            (ends, false)
+        });
+        for ends_at in ends {
+            schedule_flush(&app, ends_at);
+        }
+    });
+}
+
+/// Deliver queued notifications whose quiet hours have ended, one digest per set of sinks
+pub async fn flush_digests(app: &AppHandle) {
+    let now = Utc::now();
+    let ready: Vec<QueuedNotification> = with_queue(|queue| {
+        let (ready, waiting): (Vec<_>, Vec<_>) = queue
+            .drain(..)
+            .partition(|queued| !queued.quiet_hours.is_active_at(now));
+        *queue = waiting;
+        let changed = !ready.is_empty();
+        (ready, changed)
+    });
+    if ready.is_empty() {
+        return;
+    }
+
+    let mut groups: Vec<(Option<Vec<NotificationSinkConfig>>, Vec<QueuedNotification>)> =
+        Vec::new();
+    for queued in ready {
+        match groups
+            .iter_mut()
+            .find(|(sinks, _)| *sinks == queued.settings.sinks)
+        {
+            Some((_, entries)) => entries.push(queued),
+            None => groups.push((queued.settings.sinks.clone(), vec![queued])),
+        }
+    }
+
+    for (_, entries) in groups {
+        let settings = entries[0].settings.clone();
+        let message = digest_message(&entries);
+        let results = deliver_notification(app, &settings, &message).await;
+        if !results.is_empty() && results.iter().all(|r| !r.success) {
+            eprintln!(
+                "[Notifications] Failed to deliver digest of {} notifications",
+                entries.len()
+            );
+        }
+    }
+}
+
+fn digest_message(entries: &[QueuedNotification]) -> NotificationMessage {
+    let first = &entries[0].message.account_name;
+    let account_name = if entries.iter().all(|e| &e.message.account_name == first) {
+        first.clone()
+    } else {
+        "multiple accounts".to_string()
+    };
+
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|e| format!("- {}: {}", e.message.account_name, e.message.body))
+        .collect();
+
+    let mut message = NotificationMessage::new(&account_name, "digest", lines.join("\n"));
+    let first_id = &entries[0].message.account_id;
+    if entries.iter().all(|e| &e.message.account_id == first_id) {
+        message.account_id = first_id.clone();
+    }
+    message.title = format!(
+        "Codex Switcher: {} notifications during quiet hours",
+        entries.len()
+    );
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn berlin_nights() -> QuietHours {
+        QuietHours {
+            enabled: true,
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+            weekdays: Vec::new(),
+            time_zone: Some("Europe/Berlin".to_string()),
+            allow_critical: true,
+        }
+    }
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn follows_daylight_saving_time() {
+        let quiet_hours = berlin_nights();
+
+        // 06:30 CET, the day before clocks go forward
+        assert!(quiet_hours.is_active_at(utc(2026, 3, 28, 5, 30)));
+        // 07:30 CEST, the same UTC time once summer time started
+        assert!(!quiet_hours.is_active_at(utc(2026, 3, 29, 5, 30)));
+        // 23:30 CEST
+        assert!(quiet_hours.is_active_at(utc(2026, 7, 10, 21, 30)));
+    }
+
+    #[test]
+    fn ends_at_local_end_time() {
+        let quiet_hours = berlin_nights();
+
+        assert_eq!(
+            quiet_hours.ends_after(utc(2026, 1, 10, 5, 30)),
+            Some(utc(2026, 1, 10, 6, 0))
+        );
+        assert_eq!(
+            quiet_hours.ends_after(utc(2026, 7, 10, 4, 30)),
+            Some(utc(2026, 7, 10, 5, 0))
+        );
+        assert_eq!(quiet_hours.ends_after(utc(2026, 7, 10, 12, 0)), None);
+    }
+
+    #[test]
+    fn equal_start_and_end_is_rejected_and_never_active() {
+        let mut quiet_hours = berlin_nights();
+        quiet_hours.end = quiet_hours.start.clone();
+
+        assert!(validate_quiet_hours(&quiet_hours).is_err());
+        assert!(!quiet_hours.is_active_at(utc(2026, 1, 10, 21, 0)));
+        assert_eq!(quiet_hours.ends_after(utc(2026, 1, 10, 21, 0)), None);
+    }
+
+    #[test]
+    fn rejects_unknown_time_zones() {
+        assert!(validate_quiet_hours(&berlin_nights()).is_ok());
+
+        let mut quiet_hours = berlin_nights();
+        quiet_hours.time_zone = Some("Mars/Olympus_Mons".to_string());
+        assert!(validate_quiet_hours(&quiet_hours).is_err());
+    }
+
+    #[test]
+    fn queued_notifications_survive_serialization() {
+        let queued = QueuedNotification {
+            settings: NotificationSettings::default(),
+            quiet_hours: berlin_nights(),
+            message: NotificationMessage::new("Work", "usage_level", "90% used".to_string())
+                .for_account("acc-1")
+                .with_value(90.0),
+        };
+
+        let json = serde_json::to_string(&vec![queued]).unwrap();
+        let restored: Vec<QueuedNotification> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].quiet_hours, berlin_nights());
+        assert_eq!(restored[0].message.account_id.as_deref(), Some("acc-1"));
+        assert_eq!(restored[0].message.body, "90% used");
+        assert_eq!(restored[0].message.value, Some(90.0));
+    }
+}
@@ -0,0 +1,131 @@
+//! Notifications when an exhausted usage window resets
+//!
+//! A timer is scheduled from the window's reset timestamp, so the notification
+//! arrives on time instead of at the next usage poll.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Utc;
+use tauri::AppHandle;
+use tokio::task::AbortHandle;
+
+use super::{effective_notification_settings, send_reset_notification};
+use crate::auth::get_account;
+use crate::types::{StoredAccount, UsageInfo};
+
+/// Usage at or above this percentage counts as exhausted
+const EXHAUSTED_PERCENT: f64 = 100.0;
+
+/// Wait a little past the reset so the backend has caught up
+const RESET_GRACE_SECS: u64 = 5;
+
+struct ScheduledReset {
+    resets_at: i64,
+    task: AbortHandle,
+}
+
+/// Pending reset timers keyed by (account ID, window)
+static SCHEDULED: Mutex<BTreeMap<(String, &'static str), ScheduledReset>> =
+    Mutex::new(BTreeMap::new());
+
+/// Schedule a notification for every exhausted window of an account.
+/// Rescheduling the same reset is a no-op; a changed reset replaces the timer.
+pub fn schedule_reset_notifications(app: &AppHandle, account: &StoredAccount, usage: &UsageInfo) {
+    let settings = effective_notification_settings(account);
+    if !settings.enabled || !settings.notify_on_reset {
+        cancel_reset_notifications(&account.id);
+        return;
+    }
+
+    let windows = [
+        (
+            "primary",
+            "Primary rate limit",
+            usage.primary_used_percent,
+            usage.primary_resets_at,
+        ),
+        (
+            "secondary",
+            "Secondary rate limit",
+            usage.secondary_used_percent,
+            usage.secondary_resets_at,
+        ),
+    ];
+
+    let now = Utc::now().timestamp();
+    for (window, label, used_percent, resets_at) in windows {
+        let exhausted = used_percent.is_some_and(|used| used >= EXHAUSTED_PERCENT);
+        match resets_at {
+            Some(resets_at) if exhausted && resets_at > now => {
+                schedule(app, &account.id, window, label, resets_at);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Drop all pending reset timers of an account (e.g. after it was deleted)
+pub fn cancel_reset_notifications(account_id: &str) {
+    let mut scheduled = SCHEDULED.lock().unwrap();
+    scheduled.retain(|(id, _), pending| {
+        if id == account_id {
+            pending.task.abort();
+            false
+        } else {
+            true
+        }
+    });
+}
+
+fn schedule(
+    app: &AppHandle,
+    account_id: &str,
+    window: &'static str,
+    label: &'static str,
+    resets_at: i64,
+) {
+    let key = (account_id.to_string(), window);
+    let mut scheduled = SCHEDULED.lock().unwrap();
+
+    if let Some(pending) = scheduled.get(&key) {
+        if pending.resets_at == resets_at {
+            return;
+        }
+        pending.task.abort();
+    }
+
+    let delay_secs = u64::try_from(resets_at - Utc::now().timestamp()).unwrap_or(0);
+    let app = app.clone();
+    let task_key = key.clone();
+
+    let task = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(delay_secs + RESET_GRACE_SECS)).await;
+        SCHEDULED.lock().unwrap().remove(&task_key);
+
+        // Settings may have changed, or the account removed, while we waited
+        let account = match get_account(&task_key.0) {
+            Ok(Some(account)) => account,
+            _ => return,
+        };
+        let settings = effective_notification_settings(&account);
+        if !settings.enabled || !settings.notify_on_reset {
+            return;
+        }
+
+        println!(
+            "[Notifications] {} window reset for account {}",
+            task_key.1, account.name
+        );
+        send_reset_notification(&app, &settings, &account.id, &account.name, label);
+    });
+
+    scheduled.insert(
+        key,
+        ScheduledReset {
+            resets_at,
+            task: task.abort_handle(),
+        },
+    );
+}
@@ -0,0 +1,557 @@
+//! Notification sinks: desktop, generic webhook, Slack and Discord
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use super::{
+    flush_digests, get_notification_icon_path, global_notification_settings, queue_for_digest,
+    record_notification,
+};
+use crate::api::http_client;
+use crate::types::{NotificationSettings, NotificationSinkConfig, SinkDeliveryResult};
+
+/// Delay before the first retry; doubled for every further attempt
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Total time spent retrying one sink before giving up
+const RETRY_BUDGET: Duration = Duration::from_secs(30);
+
+const DEFAULT_WEBHOOK_TEMPLATE: &str = r#"{"title": "{{title}}", "body": "{{body}}", "account": "{{account}}", "event": "{{event}}", "timestamp": "{{timestamp}}"}"#;
+
+/// A notification ready to be delivered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationMessage {
+    /// Set for notifications about one stored account
+    pub account_id: Option<String>,
+    pub account_name: String,
+    /// Event type, e.g. "usage_level" or "credits_low"
+    pub event: String,
+    pub title: String,
+    pub body: String,
+    /// The value that triggered it, e.g. used percent or credits balance
+    pub value: Option<f64>,
+    /// Critical events may bypass quiet hours
+    pub critical: bool,
+}
+
+impl NotificationMessage {
+    pub fn new(account_name: &str, event: &str, body: String) -> Self {
+        Self {
+            account_id: None,
+            account_name: account_name.to_string(),
+            event: event.to_string(),
+            title: format!("Codex Switcher: {}", account_name),
+            body,
+            value: None,
+            critical: false,
+        }
+    }
+
+    /// Tie the notification to a stored account, so its history survives renames
+    pub fn for_account(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.to_string());
+        self
+    }
+
+    /// Attach the value that triggered the notification
+    pub fn with_value(mut self, value: f64) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Use the title/body template configured for this event, if any.
+    /// `vars` fill `{{name}}` placeholders next to `{{account}}`.
+    pub fn with_template(
+        mut self,
+        settings: &NotificationSettings,
+        vars: &[(&str, String)],
+    ) -> Self {
+        let Some(template) = settings.templates.get(&self.event) else {
+            return self;
+        };
+
+        let render = |text: &str| {
+            vars.iter().fold(
+                text.replace("{{account}}", &self.account_name),
+                |text, (name, value)| text.replace(&format!("{{{{{name}}}}}"), value),
+            )
+        };
+        if let Some(title) = &template.title {
+            self.title = render(title);
+        }
+        if let Some(body) = &template.body {
+            self.body = render(body);
+        }
+        self
+    }
+
+    /// Mark the message as a critical event
+    pub fn critical(mut self) -> Self {
+        self.critical = true;
+        self
+    }
+}
+
+/// A destination notifications can be delivered to
+pub trait NotificationSink: Send + Sync {
+    /// Sink type reported in delivery results
+    fn kind(&self) -> &'static str;
+
+    fn send<'a>(&'a self, message: &'a NotificationMessage) -> BoxFuture<'a, Result<()>>;
+}
+
+/// OS native notification via tauri_plugin_notification
+pub struct DesktopSink {
+    app: AppHandle,
+}
+
+impl NotificationSink for DesktopSink {
+    fn kind(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn send<'a>(&'a self, message: &'a NotificationMessage) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let icon_path = get_notification_icon_path();
+            self.app
+                .notification()
+                .builder()
+                .title(&message.title)
+                .body(&message.body)
+                .icon(&icon_path)
+                .show()
+                .context("Failed to show desktop notification")
+        })
+    }
+}
+
+/// JSON POST with a templated body
+pub struct WebhookSink {
+    url: String,
+    body_template: Option<String>,
+    headers: BTreeMap<String, String>,
+}
+
+impl NotificationSink for WebhookSink {
+    fn kind(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn send<'a>(&'a self, message: &'a NotificationMessage) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let template = self
+                .body_template
+                .as_deref()
+                .unwrap_or(DEFAULT_WEBHOOK_TEMPLATE);
+            let body: serde_json::Value = serde_json::from_str(&render_template(template, message))
+                .context("Webhook body template did not render valid JSON")?;
+
+            let mut request = http_client()?.post(&self.url).json(&body);
+            for (name, value) in &self.headers {
+                request = request.header(name, value);
+            }
+            post_json(request).await
+        })
+    }
+}
+
+/// Slack incoming webhook
+pub struct SlackSink {
+    webhook_url: String,
+}
+
+impl NotificationSink for SlackSink {
+    fn kind(&self) -> &'static str {
+        "slack"
+    }
+
+    fn send<'a>(&'a self, message: &'a NotificationMessage) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let body = json!({ "text": format!("*{}*\n{}", message.title, message.body) });
+            post_json(http_client()?.post(&self.webhook_url).json(&body)).await
+        })
+    }
+}
+
+/// Discord channel webhook
+pub struct DiscordSink {
+    webhook_url: String,
+}
+
+impl NotificationSink for DiscordSink {
+    fn kind(&self) -> &'static str {
+        "discord"
+    }
+
+    fn send<'a>(&'a self, message: &'a NotificationMessage) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let body = json!({ "content": format!("**{}**\n{}", message.title, message.body) });
+            post_json(http_client()?.post(&self.webhook_url).json(&body)).await
+        })
+    }
+}
+
+async fn post_json(request: reqwest::RequestBuilder) -> Result<()> {
+    let response = request
+        .send()
+        .await
+        .context("Failed to send webhook request")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let snippet: String = body.chars().take(200).collect();
+        anyhow::bail!("Webhook returned {status}: {snippet}");
+    }
+    Ok(())
+}
+
+/// Substitute `{{placeholder}}`s with JSON-escaped message fields
+fn render_template(template: &str, message: &NotificationMessage) -> String {
+    let escape = |value: &str| {
+        let quoted = serde_json::Value::String(value.to_string()).to_string();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+
+    template
+        .replace("{{title}}", &escape(&message.title))
+        .replace("{{body}}", &escape(&message.body))
+        .replace("{{account}}", &escape(&message.account_name))
+        .replace("{{event}}", &escape(&message.event))
+        .replace("{{timestamp}}", &Utc::now().to_rfc3339())
+}
+
+/// Build a sink from its configuration
+pub fn build_sink(app: &AppHandle, config: &NotificationSinkConfig) -> Box<dyn NotificationSink> {
+    match config {
+        NotificationSinkConfig::Desktop => Box::new(DesktopSink { app: app.clone() }),
+        NotificationSinkConfig::Webhook {
+            url,
+            body_template,
+            headers,
+        } => Box::new(WebhookSink {
+            url: url.clone(),
+            body_template: body_template.clone(),
+            headers: headers.clone(),
+        }),
+        NotificationSinkConfig::Slack { webhook_url } => Box::new(SlackSink {
+            webhook_url: webhook_url.clone(),
+        }),
+        NotificationSinkConfig::Discord { webhook_url } => Box::new(DiscordSink {
+            webhook_url: webhook_url.clone(),
+        }),
+    }
+}
+
+/// Validate a sink configuration before saving it
+pub fn validate_sink_config(config: &NotificationSinkConfig) -> Result<()> {
+    let (field, url) = match config {
+        NotificationSinkConfig::Desktop => return Ok(()),
+        NotificationSinkConfig::Webhook { url, .. } => ("url", url),
+        NotificationSinkConfig::Slack { webhook_url }
+        | NotificationSinkConfig::Discord { webhook_url } => ("webhook_url", webhook_url),
+    };
+
+    let parsed =
+        url::Url::parse(url.trim()).with_context(|| format!("{field} is not a valid URL"))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        anyhow::bail!("{field} must be an http(s) URL");
+    }
+
+    if let NotificationSinkConfig::Webhook {
+        body_template: Some(template),
+        ..
+    } = config
+    {
+        let sample = NotificationMessage::new("Test", "test", "Test".to_string());
+        serde_json::from_str::<serde_json::Value>(&render_template(template, &sample))
+            .context("body_template must render valid JSON")?;
+    }
+
+    Ok(())
+}
+
+/// Deliver a message to every sink configured for the account, retrying failures.
+/// The outcome is recorded in the notification history.
+pub async fn deliver_notification(
+    app: &AppHandle,
+    settings: &NotificationSettings,
+    message: &NotificationMessage,
+) -> Vec<SinkDeliveryResult> {
+    let global = global_notification_settings();
+    let configs = settings.sinks.as_ref().unwrap_or(&global.sinks);
+
+    let mut results = Vec::with_capacity(configs.len());
+    for config in configs {
+        let sink = build_sink(app, config);
+        results.push(
+            send_with_retry(
+                sink.as_ref(),
+                message,
+                global.delivery_retries,
+                RETRY_BUDGET,
+            )
+            .await,
+        );
+    }
+    record_notification(message, results.clone());
+    results
+}
+
+/// Send with exponential backoff, up to `retries` extra attempts and at most
+/// `budget` spent waiting between them
+async fn send_with_retry(
+    sink: &dyn NotificationSink,
+    message: &NotificationMessage,
+    retries: u8,
+    budget: Duration,
+) -> SinkDeliveryResult {
+    let started = Instant::now();
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let delay = Duration::from_millis(RETRY_BASE_DELAY_MS << (attempts - 1).min(6));
+        match sink.send(message).await {
+            Ok(()) => {
+                return SinkDeliveryResult {
+                    sink: sink.kind().to_string(),
+                    success: true,
+                    attempts,
+                    error: None,
+                }
+            }
+            Err(e) if attempts > retries || started.elapsed() + delay > budget => {
+                println!(
+                    "[Notifications] {} delivery failed after {} attempts: {e:#}",
+                    sink.kind(),
+                    attempts
+                );
+                return SinkDeliveryResult {
+                    sink: sink.kind().to_string(),
+                    success: false,
+                    attempts,
+                    error: Some(format!("{e:#}")),
+                };
+            }
+            Err(e) => {
+                println!(
+                    "[Notifications] {} delivery attempt {} failed: {e:#}",
+                    sink.kind(),
+                    attempts
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Deliver a message in the background, or queue it for the digest during quiet hours.
+/// Returns immediately so slow or dead sinks never hold up the caller.
+pub fn notify(app: &AppHandle, settings: &NotificationSettings, message: NotificationMessage) {
+    let app = app.clone();
+    let settings = settings.clone();
+    tokio::spawn(async move {
+        let account_name = message.account_name.clone();
+        if let Err(e) = notify_now(&app, &settings, message).await {
+            eprintln!(
+                "[Notifications] Failed to deliver notification for {}: {}",
+                account_name, e
+            );
+        }
+    });
+}
+
+/// Deliver a message now, or queue it for the digest during quiet hours.
+/// Fails only when no sink accepted it.
+pub async fn notify_now(
+    app: &AppHandle,
+    settings: &NotificationSettings,
+    message: NotificationMessage,
+) -> Result<(), String> {
+    flush_digests(app).await;
+
+    let quiet_hours = settings
+        .quiet_hours
+        .clone()
+        .or_else(|| global_notification_settings().quiet_hours);
+    if let Some(quiet_hours) = quiet_hours.filter(|q| q.is_active_at(Utc::now())) {
+        if !(message.critical && quiet_hours.allow_critical) {
+            queue_for_digest(app, settings, &quiet_hours, message);
+            return Ok(());
+        }
+    }
+
+    let results = deliver_notification(app, settings, &message).await;
+    if results.is_empty() || results.iter().any(|r| r.success) {
+        return Ok(());
+    }
+
+    let errors: Vec<String> = results
+        .iter()
+        .filter_map(|r| r.error.as_ref().map(|e| format!("{}: {}", r.sink, e)))
+        .collect();
+    Err(errors.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{StandIn, StandInResponse};
+
+    fn message() -> NotificationMessage {
+        NotificationMessage::new("Work \"main\"", "usage_level", "Usage at 91%".to_string())
+            .with_value(91.0)
+    }
+
+    #[tokio::test]
+    async fn webhook_posts_rendered_template_with_headers() {
+        let server = StandIn::start(vec![StandInResponse::new(200, "ok")]).await;
+        let sink = WebhookSink {
+            url: format!("{}/hook", server.url),
+            body_template: Some(r#"{"text": "{{title}}: {{body}}", "who": "{{account}}"}"#.into()),
+            headers: BTreeMap::from([("X-Token".to_string(), "secret".to_string())]),
+        };
+
+        sink.send(&message()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/hook");
+        assert_eq!(requests[0].header("x-token"), Some("secret"));
+        assert_eq!(
+            requests[0].json(),
+            json!({
+                "text": "Codex Switcher: Work \"main\": Usage at 91%",
+                "who": "Work \"main\"",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn webhook_default_template_carries_event() {
+        let server = StandIn::start(vec![StandInResponse::new(204, "")]).await;
+        let sink = WebhookSink {
+            url: server.url.clone(),
+            body_template: None,
+            headers: BTreeMap::new(),
+        };
+
+        sink.send(&message()).await.unwrap();
+
+        let body = server.requests()[0].json();
+        assert_eq!(body["event"], "usage_level");
+        assert_eq!(body["body"], "Usage at 91%");
+        assert!(body["timestamp"].as_str().is_some_and(|t| !t.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn slack_and_discord_payloads() {
+        let server = StandIn::start(vec![StandInResponse::new(200, "ok")]).await;
+        SlackSink {
+            webhook_url: server.url.clone(),
+        }
+        .send(&message())
+        .await
+        .unwrap();
+        DiscordSink {
+            webhook_url: server.url.clone(),
+        }
+        .send(&message())
+        .await
+        .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].json()["text"],
+            "*Codex Switcher: Work \"main\"*\nUsage at 91%"
+        );
+        assert_eq!(
+            requests[1].json()["content"],
+            "**Codex Switcher: Work \"main\"**\nUsage at 91%"
+        );
+    }
+
+    #[tokio::test]
+    async fn error_status_fails_with_body_snippet() {
+        let server = StandIn::start(vec![StandInResponse::new(404, "no such hook")]).await;
+        let sink = SlackSink {
+            webhook_url: server.url.clone(),
+        };
+
+        let err = sink.send(&message()).await.unwrap_err();
+        assert!(err.to_string().contains("404"));
+        assert!(err.to_string().contains("no such hook"));
+    }
+
+    #[tokio::test]
+    async fn retries_until_delivered() {
+        let server = StandIn::start(vec![
+            StandInResponse::new(500, "down"),
+            StandInResponse::new(200, "ok"),
+        ])
+        .await;
+        let sink = SlackSink {
+            webhook_url: server.url.clone(),
+        };
+
+        let result = send_with_retry(&sink, &message(), 3, RETRY_BUDGET).await;
+        assert!(result.success);
+        assert_eq!(result.attempts, 2);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_retries() {
+        let server = StandIn::start(vec![StandInResponse::new(500, "down")]).await;
+        let sink = SlackSink {
+            webhook_url: server.url.clone(),
+        };
+
+        let result = send_with_retry(&sink, &message(), 1, RETRY_BUDGET).await;
+        assert!(!result.success);
+        assert_eq!(result.attempts, 2);
+        assert!(result.error.unwrap().contains("500"));
+    }
+
+    #[tokio::test]
+    async fn stops_retrying_when_budget_is_spent() {
+        let server = StandIn::start(vec![StandInResponse::new(500, "down")]).await;
+        let sink = SlackSink {
+            webhook_url: server.url.clone(),
+        };
+
+        let started = Instant::now();
+        let result = send_with_retry(&sink, &message(), 10, Duration::from_millis(1200)).await;
+        assert!(!result.success);
+        // 500ms + 1000ms would exceed the budget after the second attempt
+        assert_eq!(result.attempts, 2);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn validates_sink_urls_and_templates() {
+        assert!(validate_sink_config(&NotificationSinkConfig::Slack {
+            webhook_url: "ftp://example.com".into()
+        })
+        .is_err());
+        assert!(validate_sink_config(&NotificationSinkConfig::Webhook {
+            url: "https://example.com/hook".into(),
+            body_template: Some("{\"text\": {{body}}}".into()),
+            headers: BTreeMap::new(),
+        })
+        .is_err());
+        assert!(validate_sink_config(&NotificationSinkConfig::Discord {
+            webhook_url: "https://discord.com/api/webhooks/1/x".into()
+        })
+        .is_ok());
+    }
+}
@@ -0,0 +1,23 @@
+//! In-memory process list for exercising process matching without real processes
+
+use anyhow::Result;
+
+use super::{ProcessInfo, ProcessScanner};
+
+/// Scanner returning a fixed process list
+#[derive(Debug, Clone, Default)]
+pub struct FakeProcessScanner {
+    pub processes: Vec<ProcessInfo>,
+}
+
+impl FakeProcessScanner {
+    pub fn new(processes: Vec<ProcessInfo>) -> Self {
+        Self { processes }
+    }
+}
+
+impl ProcessScanner for FakeProcessScanner {
+    fn scan(&self) -> Result<Vec<ProcessInfo>> {
+        Ok(self.processes.clone())
+    }
+}
@@ -0,0 +1,275 @@
+//! Running process discovery: which Codex CLI and IDE app-server processes
+//! exist, and which Codex home each of them uses. Also restarts app-servers
+//! after switching accounts.
+
+#[cfg(test)]
+mod fake;
+#[cfg(target_os = "linux")]
+mod proc_fs;
+#[cfg(unix)]
+mod ps;
+mod restart;
+
+#[cfg(test)]
+pub use fake::*;
+#[cfg(target_os = "linux")]
+pub use proc_fs::*;
+#[cfg(unix)]
+pub use ps::*;
+pub use restart::*;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+/// IDE extension directories that bundle a Codex app-server
+const IDE_EXTENSION_DIRS: [&str; 2] = [
+    ".antigravity/extensions/openai.chatgpt",
+    ".vscode/extensions/openai.chatgpt",
+];
+
+/// A running process as seen by a [`ProcessScanner`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// Resolved executable path, when readable
+    pub exe: Option<PathBuf>,
+    /// Argument vector, argv[0] first
+    pub cmdline: Vec<String>,
+    /// Working directory, when readable
+    pub cwd: Option<PathBuf>,
+    /// Codex home from the process environment (`CODEX_HOME`, else `$HOME/.codex`)
+    pub codex_home: Option<PathBuf>,
+}
+
+impl ProcessInfo {
+    /// Executable and arguments joined for substring matching
+    pub fn command_line(&self) -> String {
+        let exe = self.exe.as_ref().map(|p| p.to_string_lossy().into_owned());
+        exe.into_iter()
+            .chain(self.cmdline.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether the executable (or argv[0]) is named exactly "codex"
+    pub fn is_codex(&self) -> bool {
+        let is_codex_path = |path: &Path| path.file_name().is_some_and(|name| name == "codex");
+        self.exe.as_deref().is_some_and(is_codex_path)
+            || self
+                .cmdline
+                .first()
+                .is_some_and(|arg| is_codex_path(Path::new(arg)))
+    }
+
+    /// Whether the process belongs to Codex Switcher itself
+    pub fn is_switcher(&self) -> bool {
+        let command = self.command_line();
+        command.contains("codex-switcher") || command.contains("Codex Switcher")
+    }
+
+    /// Whether the process runs from an IDE extension or integration
+    pub fn is_ide_plugin(&self) -> bool {
+        let command = self.command_line();
+        command.contains(".antigravity")
+            || command.contains("openai.chatgpt")
+            || command.contains(".vscode")
+    }
+
+    /// Whether this is the `codex app-server` bundled with an IDE extension
+    pub fn is_ide_app_server(&self) -> bool {
+        let command = self.command_line();
+        self.is_codex()
+            && self.cmdline.get(1).is_some_and(|arg| arg == "app-server")
+            && IDE_EXTENSION_DIRS.iter().any(|dir| command.contains(dir))
+    }
+
+    /// Whether the process reads credentials from `codex_home`.
+    /// Assumed true when its environment could not be read.
+    pub fn uses_codex_home(&self, codex_home: &Path) -> bool {
+        self.codex_home
+            .as_deref()
+            .is_none_or(|home| same_path(home, codex_home))
+    }
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Source of the running process list
+pub trait ProcessScanner: Send + Sync {
+    fn scan(&self) -> Result<Vec<ProcessInfo>>;
+}
+
+/// The scanner for this platform: `/proc` on Linux, `ps` elsewhere
+#[cfg(unix)]
+pub fn default_scanner() -> Box<dyn ProcessScanner> {
+    #[cfg(target_os = "linux")]
+    if Path::new("/proc/self").exists() {
+        return Box::new(ProcFsScanner::new());
+    }
+    Box::new(PsScanner)
+}
+
+/// Running Codex CLI processes sharing `codex_home`
+#[derive(Debug, Clone, Default)]
+pub struct CodexProcesses {
+    /// Interactive processes that block switching
+    pub active: Vec<u32>,
+    /// Background IDE/extension processes (like Antigravity)
+    pub background: Vec<u32>,
+}
+
+/// Find running codex processes that use `codex_home`, excluding our own
+pub fn find_codex_processes(
+    scanner: &dyn ProcessScanner,
+    codex_home: &Path,
+) -> Result<CodexProcesses> {
+    let mut found = CodexProcesses::default();
+
+    for process in scanner.scan()? {
+        if process.pid == std::process::id()
+            || !process.is_codex()
+            || process.is_switcher()
+            || !process.uses_codex_home(codex_home)
+            || found.active.contains(&process.pid)
+            || found.background.contains(&process.pid)
+        {
+            continue;
+        }
+
+        if process.is_ide_plugin() {
+            found.background.push(process.pid);
+        } else {
+            found.active.push(process.pid);
+        }
+    }
+
+    Ok(found)
+}
+
+/// Find IDE app-server processes (Antigravity, VS Code) that use `codex_home`
+pub fn find_ide_app_servers(
+    scanner: &dyn ProcessScanner,
+    codex_home: &Path,
+) -> Result<Vec<ProcessInfo>> {
+    Ok(scanner
+        .scan()?
+        .into_iter()
+        .filter(|p| p.is_ide_app_server() && p.uses_codex_home(codex_home))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOME: &str = "/home/dev/.codex";
+
+    fn process(pid: u32, cmdline: &[&str], codex_home: Option<&str>) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            exe: cmdline.first().map(PathBuf::from),
+            cmdline: cmdline.iter().map(|arg| arg.to_string()).collect(),
+            cwd: None,
+            codex_home: codex_home.map(PathBuf::from),
+        }
+    }
+
+    fn scan(processes: Vec<ProcessInfo>) -> CodexProcesses {
+        find_codex_processes(&FakeProcessScanner::new(processes), Path::new(HOME)).unwrap()
+    }
+
+    #[test]
+    fn finds_codex_with_spaces_in_argv() {
+        let found = scan(vec![
+            process(
+                10,
+                &["/Users/dev/Application Support/bin/codex", "--model", "o3"],
+                Some(HOME),
+            ),
+            process(11, &["/usr/bin/vim", "notes about codex.md"], Some(HOME)),
+            process(12, &["/opt/my codex/codex-helper"], Some(HOME)),
+        ]);
+
+        assert_eq!(found.active, vec![10]);
+        assert!(found.background.is_empty());
+    }
+
+    #[test]
+    fn filters_by_codex_home() {
+        let found = scan(vec![
+            process(20, &["codex"], Some(HOME)),
+            process(21, &["codex"], Some("/home/dev/work-codex")),
+            // Environment unreadable: assume it shares the home
+            process(22, &["codex"], None),
+        ]);
+
+        assert_eq!(found.active, vec![20, 22]);
+    }
+
+    #[test]
+    fn excludes_the_switcher_itself() {
+        let found = scan(vec![
+            process(std::process::id(), &["codex"], Some(HOME)),
+            process(
+                30,
+                &["/Applications/Codex Switcher.app/Contents/MacOS/codex"],
+                Some(HOME),
+            ),
+            process(31, &["/opt/codex-switcher/bin/codex"], Some(HOME)),
+            process(32, &["codex", "exec"], Some(HOME)),
+        ]);
+
+        assert_eq!(found.active, vec![32]);
+    }
+
+    #[test]
+    fn ide_processes_count_as_background() {
+        let found = scan(vec![
+            process(
+                40,
+                &[
+                    "/home/dev/.vscode/extensions/openai.chatgpt-1.0/bin/codex",
+                    "app-server",
+                ],
+                Some(HOME),
+            ),
+            process(41, &["codex"], Some(HOME)),
+            process(41, &["codex"], Some(HOME)),
+        ]);
+
+        assert_eq!(found.active, vec![41]);
+        assert_eq!(found.background, vec![40]);
+    }
+
+    #[test]
+    fn finds_ide_app_servers_using_codex_home() {
+        let server = |pid, dir: &str, codex_home| {
+            let exe = format!("/home/dev/{dir}/openai.chatgpt-1.0/bin/linux x64/codex");
+            process(pid, &[exe.as_str(), "app-server"], codex_home)
+        };
+        let scanner = FakeProcessScanner::new(vec![
+            server(50, ".vscode/extensions", Some(HOME)),
+            server(51, ".antigravity/extensions", None),
+            server(52, ".vscode/extensions", Some("/home/dev/other")),
+            server(53, "Downloads", Some(HOME)),
+            process(
+                54,
+                &["/home/dev/.vscode/extensions/openai.chatgpt-1.0/bin/codex"],
+                Some(HOME),
+            ),
+        ]);
+
+        let pids: Vec<u32> = find_ide_app_servers(&scanner, Path::new(HOME))
+            .unwrap()
+            .iter()
+            .map(|p| p.pid)
+            .collect();
+        assert_eq!(pids, vec![50, 51]);
+    }
+}
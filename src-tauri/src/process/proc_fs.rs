@@ -0,0 +1,161 @@
+//! Linux process scanner reading `/proc/<pid>/{exe,cmdline,cwd,environ}`
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::{ProcessInfo, ProcessScanner};
+
+/// Scans processes through procfs, without forking `ps`
+#[derive(Debug, Clone)]
+pub struct ProcFsScanner {
+    root: PathBuf,
+}
+
+impl ProcFsScanner {
+    pub fn new() -> Self {
+        Self::with_root("/proc")
+    }
+
+    /// Scan a procfs mounted somewhere other than `/proc`
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Read one process. Fields we may not read (other users' processes)
+    /// are left empty; `None` when the process is gone or a kernel thread.
+    fn read_process(&self, pid: u32) -> Option<ProcessInfo> {
+        let dir = self.root.join(pid.to_string());
+
+        let cmdline = split_nul(&fs::read(dir.join("cmdline")).ok()?);
+        if cmdline.is_empty() {
+            return None;
+        }
+
+        let exe = fs::read_link(dir.join("exe")).ok().map(strip_deleted);
+        let cwd = fs::read_link(dir.join("cwd")).ok();
+        let codex_home = fs::read(dir.join("environ"))
+            .ok()
+            .and_then(|environ| codex_home_from_environ(&split_nul(&environ), cwd.as_deref()));
+
+        Some(ProcessInfo {
+            pid,
+            exe,
+            cmdline,
+            cwd,
+            codex_home,
+        })
+    }
+}
+
+impl Default for ProcFsScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessScanner for ProcFsScanner {
+    fn scan(&self) -> Result<Vec<ProcessInfo>> {
+        let entries = fs::read_dir(&self.root)
+            .with_context(|| format!("Failed to read {}", self.root.display()))?;
+
+        Ok(entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+            .filter_map(|pid| self.read_process(pid))
+            .collect())
+    }
+}
+
+/// Split a NUL-separated procfs file, dropping the trailing terminator
+fn split_nul(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .split(|b| *b == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).into_owned())
+        .collect()
+}
+
+/// The kernel appends " (deleted)" when the binary was replaced on disk
+fn strip_deleted(path: PathBuf) -> PathBuf {
+    match path.to_str().and_then(|p| p.strip_suffix(" (deleted)")) {
+        Some(stripped) => PathBuf::from(stripped),
+        None => path,
+    }
+}
+
+/// Resolve the Codex home the way the Codex CLI does: `CODEX_HOME`
+/// (relative to the working directory), falling back to `$HOME/.codex`
+fn codex_home_from_environ(environ: &[String], cwd: Option<&Path>) -> Option<PathBuf> {
+    let var = |name: &str| {
+        environ
+            .iter()
+            .find_map(|entry| entry.strip_prefix(name)?.strip_prefix('='))
+            .filter(|value| !value.is_empty())
+    };
+
+    if let Some(codex_home) = var("CODEX_HOME") {
+        let path = PathBuf::from(codex_home);
+        return match cwd {
+            Some(cwd) if path.is_relative() => Some(cwd.join(path)),
+            _ => Some(path),
+        };
+    }
+    var("HOME").map(|home| Path::new(home).join(".codex"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_argv_with_spaces_and_codex_home() {
+        let root = std::env::temp_dir().join(format!("procfs-{}", uuid::Uuid::new_v4()));
+        let dir = root.join("4242");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("cmdline"),
+            b"/opt/my tools/codex\0exec\0fix the bug\0",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("environ"),
+            b"HOME=/home/dev\0CODEX_HOME=/srv/codex home\0",
+        )
+        .unwrap();
+
+        let processes = ProcFsScanner::with_root(&root).scan().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(processes.len(), 1);
+        let process = &processes[0];
+        assert_eq!(process.pid, 4242);
+        assert_eq!(
+            process.cmdline,
+            vec!["/opt/my tools/codex", "exec", "fix the bug"]
+        );
+        assert!(process.is_codex());
+        assert_eq!(process.codex_home, Some(PathBuf::from("/srv/codex home")));
+    }
+
+    #[test]
+    fn falls_back_to_home_and_resolves_relative_codex_home() {
+        let environ = |vars: &[&str]| vars.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            codex_home_from_environ(&environ(&["HOME=/home/dev"]), None),
+            Some(PathBuf::from("/home/dev/.codex"))
+        );
+        assert_eq!(
+            codex_home_from_environ(
+                &environ(&["HOME=/home/dev", "CODEX_HOME=profiles/work"]),
+                Some(Path::new("/srv"))
+            ),
+            Some(PathBuf::from("/srv/profiles/work"))
+        );
+        assert_eq!(
+            codex_home_from_environ(&environ(&["CODEX_HOME="]), None),
+            None
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! Fallback process scanner parsing `ps` output, for Unix systems without procfs
+
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+use super::{ProcessInfo, ProcessScanner};
+
+/// Scans processes with `ps -eo pid=,command=`. Arguments are split on
+/// whitespace and the environment is not available, so matching is coarser
+/// than with procfs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PsScanner;
+
+impl ProcessScanner for PsScanner {
+    fn scan(&self) -> Result<Vec<ProcessInfo>> {
+        let output = Command::new("ps")
+            .args(["-eo", "pid=,command="])
+            .output()
+            .context("Failed to run ps")?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout
+            .lines()
+            .filter_map(|line| {
+                let (pid, command) = line.trim().split_once(' ')?;
+                Some(ProcessInfo {
+                    pid: pid.trim().parse().ok()?,
+                    cmdline: command.split_whitespace().map(str::to_string).collect(),
+                    ..Default::default()
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,143 @@
+//! Graceful termination of IDE app-server processes. The IDE extension
+//! starts a fresh app-server, which picks up the newly written auth.json.
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use anyhow::{Context, Result};
+
+use crate::types::AppServerRestartResult;
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// How often to check whether a signalled process has exited
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long to wait for a process to disappear after SIGKILL
+const KILL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Ask processes to exit (SIGTERM), and SIGKILL those still running after `timeout`
+pub async fn terminate_processes(pids: &[u32], timeout: Duration) -> AppServerRestartResult {
+    let mut result = AppServerRestartResult::default();
+    // Already gone: nothing to restart
+    let pids: Vec<u32> = pids
+        .iter()
+        .copied()
+        .filter(|pid| is_running(*pid))
+        .collect();
+    if pids.is_empty() {
+        return result;
+    }
+
+    for &pid in &pids {
+        if let Err(e) = send_terminate(pid, false) {
+            println!("[Process] Failed to terminate {pid}: {e:#}");
+        }
+    }
+    let stubborn = wait_for_exit(pids.clone(), timeout).await;
+    result.restarted_pids = pids
+        .iter()
+        .copied()
+        .filter(|pid| !stubborn.contains(pid))
+        .collect();
+
+    for &pid in &stubborn {
+        println!("[Process] {pid} did not exit within {timeout:?}, killing it");
+        if let Err(e) = send_terminate(pid, true) {
+            println!("[Process] Failed to kill {pid}: {e:#}");
+        }
+    }
+    let survivors = wait_for_exit(stubborn.clone(), KILL_TIMEOUT).await;
+    for pid in stubborn {
+        if survivors.contains(&pid) {
+            result.failed_pids.push(pid);
+        } else {
+            result.restarted_pids.push(pid);
+            result.forced_pids.push(pid);
+        }
+    }
+
+    println!(
+        "[Process] Restarted app-servers {:?} (forced: {:?}, failed: {:?})",
+        result.restarted_pids, result.forced_pids, result.failed_pids
+    );
+    result
+}
+
+/// Wait until the processes exit or `timeout` passes; returns those still running
+async fn wait_for_exit(mut pids: Vec<u32>, timeout: Duration) -> Vec<u32> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        pids.retain(|pid| is_running(*pid));
+        if pids.is_empty() || Instant::now() >= deadline {
+            return pids;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// SIGTERM, or SIGKILL when `force` is set
+#[cfg(unix)]
+fn send_terminate(pid: u32, force: bool) -> Result<()> {
+    let signal = if force { "-KILL" } else { "-TERM" };
+    let status = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .context("Failed to run kill")?;
+    anyhow::ensure!(status.success(), "kill {signal} {pid} exited with {status}");
+    Ok(())
+}
+
+/// Close request, or forced termination when `force` is set
+#[cfg(windows)]
+fn send_terminate(pid: u32, force: bool) -> Result<()> {
+    let pid = pid.to_string();
+    let mut args = vec!["/PID", pid.as_str()];
+    if force {
+        args.insert(0, "/F");
+    }
+    let status = Command::new("taskkill")
+        .creation_flags(CREATE_NO_WINDOW)
+        .args(&args)
+        .status()
+        .context("Failed to run taskkill")?;
+    anyhow::ensure!(status.success(), "taskkill {pid} exited with {status}");
+    Ok(())
+}
+
+/// Whether the process still exists; zombies count as exited
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else {
+        return false;
+    };
+    // The state follows the parenthesized command name, which may contain spaces
+    stat.rsplit_once(')')
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .is_some_and(|state| state != "Z" && state != "X")
+}
+
+/// Whether the process still exists
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_running(pid: u32) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Whether the process still exists
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    Command::new("tasklist")
+        .creation_flags(CREATE_NO_WINDOW)
+        .args(["/FI", &format!("PID eq {pid}"), "/FO", "CSV", "/NH"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{pid}\"")))
+}
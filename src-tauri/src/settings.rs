@@ -0,0 +1,190 @@
+//! App settings module - manages settings.json and resolves endpoint configuration
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+
+use crate::auth::storage::get_config_dir;
+use crate::notifications::{
+    validate_notification_settings, validate_quiet_hours, validate_sink_config,
+};
+use crate::types::{AppSettings, EndpointConfig, EndpointSettings};
+
+pub const DEFAULT_ISSUER: &str = "https://auth.openai.com";
+pub const DEFAULT_CLIENT_ID: &str = "app_EMoamEEZ73f0CkXaXp7hrann";
+pub const DEFAULT_CHATGPT_BACKEND_API: &str = "https://chatgpt.com/backend-api";
+pub const DEFAULT_OPENAI_API: &str = "https://api.openai.com/v1";
+
+/// Environment variables that take precedence over settings.json
+const ENV_ISSUER: &str = "CODEX_SWITCHER_ISSUER";
+const ENV_CLIENT_ID: &str = "CODEX_SWITCHER_CLIENT_ID";
+const ENV_CHATGPT_BACKEND_API: &str = "CODEX_SWITCHER_CHATGPT_BACKEND_API";
+const ENV_OPENAI_API: &str = "CODEX_SWITCHER_OPENAI_API";
+
+/// Upper bound for concurrent usage requests
+const MAX_REFRESH_CONCURRENCY: usize = 32;
+
+/// Upper bound for notification delivery retries
+const MAX_DELIVERY_RETRIES: u8 = 10;
+
+/// Upper bound for waiting on an app-server to exit gracefully
+const MAX_TERMINATE_TIMEOUT_SECS: u64 = 60;
+
+/// Parsed settings.json, read once and replaced by `save_settings`.
+/// Settings are read on every HTTP request, so re-reading the file each time
+/// is wasteful.
+static SETTINGS_CACHE: Mutex<Option<AppSettings>> = Mutex::new(None);
+
+/// Get the path to settings.json
+pub fn get_settings_file() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("settings.json"))
+}
+
+/// Load the app settings, from disk on first use
+pub fn load_settings() -> Result<AppSettings> {
+    // Held while reading so a concurrent save cannot be overwritten with stale data
+    let mut cached = SETTINGS_CACHE.lock().unwrap();
+    if let Some(settings) = cached.as_ref() {
+        return Ok(settings.clone());
+    }
+
+    let settings = read_settings_file()?;
+    *cached = Some(settings.clone());
+    Ok(settings)
+}
+
+fn read_settings_file() -> Result<AppSettings> {
+    let path = get_settings_file()?;
+
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read settings file: {}", path.display()))?;
+
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse settings file: {}", path.display()))
+}
+
+/// Save the app settings to disk
+pub fn save_settings(settings: &AppSettings) -> Result<()> {
+    let mut cached = SETTINGS_CACHE.lock().unwrap();
+    // Re-read on next load if the write fails part way
+    *cached = None;
+
+    let path = get_settings_file()?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
+    }
+
+    let content = serde_json::to_string_pretty(settings).context("Failed to serialize settings")?;
+
+    fs::write(&path, content)
+        .with_context(|| format!("Failed to write settings file: {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = fs::Permissions::from_mode(0o600);
+        fs::set_permissions(&path, perms)?;
+    }
+
+    *cached = Some(settings.clone());
+    Ok(())
+}
+
+/// Resolve the endpoints to use: built-in defaults, overridden by settings.json,
+/// overridden by environment variables.
+pub fn endpoint_config() -> Result<EndpointConfig> {
+    let settings = load_settings()?;
+    Ok(resolve_endpoints(&settings.endpoints))
+}
+
+fn resolve_endpoints(overrides: &EndpointSettings) -> EndpointConfig {
+    let pick = |env_name: &str, setting: &Option<String>, default: &str| -> String {
+        let value = std::env::var(env_name)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .or_else(|| setting.clone().filter(|v| !v.trim().is_empty()))
+            .unwrap_or_else(|| default.to_string());
+        value.trim().trim_end_matches('/').to_string()
+    };
+
+    EndpointConfig {
+        issuer: pick(ENV_ISSUER, &overrides.issuer, DEFAULT_ISSUER),
+        client_id: pick(ENV_CLIENT_ID, &overrides.client_id, DEFAULT_CLIENT_ID),
+        chatgpt_backend_api: pick(
+            ENV_CHATGPT_BACKEND_API,
+            &overrides.chatgpt_backend_api,
+            DEFAULT_CHATGPT_BACKEND_API,
+        ),
+        openai_api: pick(ENV_OPENAI_API, &overrides.openai_api, DEFAULT_OPENAI_API),
+    }
+}
+
+/// Validate app settings before saving them
+pub fn validate_settings(settings: &AppSettings) -> Result<()> {
+    validate_endpoint_settings(&settings.endpoints)?;
+
+    if settings.login.callback_timeout_secs < 30 {
+        anyhow::bail!("callback_timeout_secs must be at least 30");
+    }
+
+    let http = &settings.http;
+    if http.request_timeout_secs == 0 || http.connect_timeout_secs == 0 {
+        anyhow::bail!("HTTP timeouts must be at least 1 second");
+    }
+    if let Some(proxy) = http.proxy.as_deref().filter(|v| !v.trim().is_empty()) {
+        reqwest::Proxy::all(proxy.trim()).context("proxy is not a valid proxy URL")?;
+    }
+
+    if !(1..=MAX_REFRESH_CONCURRENCY).contains(&settings.usage.refresh_concurrency) {
+        anyhow::bail!("refresh_concurrency must be between 1 and {MAX_REFRESH_CONCURRENCY}");
+    }
+
+    validate_notification_settings(&settings.notifications.defaults)
+        .context("Invalid notification defaults")?;
+    for sink in &settings.notifications.sinks {
+        validate_sink_config(sink)?;
+    }
+    if let Some(quiet_hours) = &settings.notifications.quiet_hours {
+        validate_quiet_hours(quiet_hours)?;
+    }
+    if !(1..=MAX_TERMINATE_TIMEOUT_SECS).contains(&settings.processes.terminate_timeout_secs) {
+        anyhow::bail!("terminate_timeout_secs must be between 1 and {MAX_TERMINATE_TIMEOUT_SECS}");
+    }
+    if settings.notifications.history_max_entries == 0 {
+        anyhow::bail!("history_max_entries must be at least 1");
+    }
+    if settings.notifications.delivery_retries > MAX_DELIVERY_RETRIES {
+        anyhow::bail!("delivery_retries must be at most {MAX_DELIVERY_RETRIES}");
+    }
+
+    Ok(())
+}
+
+/// Validate endpoint overrides before saving them
+pub fn validate_endpoint_settings(endpoints: &EndpointSettings) -> Result<()> {
+    let urls = [
+        ("issuer", &endpoints.issuer),
+        ("chatgpt_backend_api", &endpoints.chatgpt_backend_api),
+        ("openai_api", &endpoints.openai_api),
+    ];
+
+    for (field, value) in urls {
+        if let Some(value) = value.as_deref().filter(|v| !v.trim().is_empty()) {
+            let parsed = url::Url::parse(value.trim())
+                .with_context(|| format!("{field} is not a valid URL"))?;
+            if parsed.scheme() != "http" && parsed.scheme() != "https" {
+                anyhow::bail!("{field} must be an http(s) URL");
+            }
+        }
+    }
+
+    Ok(())
+}
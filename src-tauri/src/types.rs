@@ -1,9 +1,13 @@
 //! Core types for Codex Switcher
 
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
 
+use crate::auth::TokenTimes;
+
 /// The main storage structure for all accounts
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountsStore {
@@ -15,10 +19,13 @@ pub struct AccountsStore {
     pub active_account_id: Option<String>,
 }
 
+/// Current accounts.json schema version
+pub const ACCOUNTS_STORE_VERSION: u32 = 3;
+
 impl Default for AccountsStore {
     fn default() -> Self {
         Self {
-            version: 2,
+            version: ACCOUNTS_STORE_VERSION,
             accounts: Vec::new(),
             active_account_id: None,
         }
@@ -44,16 +51,39 @@ pub struct StoredAccount {
     pub created_at: DateTime<Utc>,
     /// Last time this account was used
     pub last_used_at: Option<DateTime<Utc>>,
-    /// Notification settings for this account
+    /// Notification settings for this account; unset fields inherit the global defaults
     #[serde(default)]
-    pub notification_settings: NotificationSettings,
+    pub notification_settings: NotificationOverrides,
     /// Last notification timestamps for cooldown tracking
     #[serde(default)]
     pub last_notifications: LastNotifications,
+    /// ChatGPT workspaces the login can use (ChatGPT auth)
+    #[serde(default)]
+    pub workspaces: Vec<AccountWorkspace>,
+    /// Workspace this account is pinned to; sent as the `chatgpt-account-id` header
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// When the OAuth tokens were last refreshed
+    #[serde(default)]
+    pub last_refresh_at: Option<DateTime<Utc>>,
+    /// When the current refresh token was obtained (login or rotation)
+    #[serde(default)]
+    pub refresh_token_issued_at: Option<DateTime<Utc>>,
 }
 
-/// Per-account notification settings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A ChatGPT workspace (organization) the login belongs to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountWorkspace {
+    pub id: String,
+    pub title: Option<String>,
+    pub role: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// Notification settings, either the global defaults or the effective
+/// settings of an account after applying its overrides
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct NotificationSettings {
     /// Enable/disable all notifications for this account
     pub enabled: bool,
@@ -61,10 +91,309 @@ pub struct NotificationSettings {
     pub primary_threshold: Option<u8>,
     /// Secondary rate limit threshold (0-100, None = disabled)
     pub secondary_threshold: Option<u8>,
-    /// Credits threshold (0-100, None = disabled)
+    /// Percentage of `credits_budget` used before notifying (0-100, None = disabled)
     pub credits_threshold: Option<u8>,
-    /// Minimum minutes between notifications for the same threshold
+    /// Notify when the credits balance drops to this amount (None = disabled)
+    #[serde(default)]
+    pub credits_balance_threshold: Option<f64>,
+    /// Currency of the credits amounts, e.g. "USD" (None = whatever the API reports)
+    #[serde(default)]
+    pub credits_currency: Option<String>,
+    /// User-defined credits budget the percentage threshold is measured against
+    #[serde(default)]
+    pub credits_budget: Option<f64>,
+    /// Minimum minutes between credits notifications
     pub min_interval_minutes: u8,
+    /// Notify when an exhausted usage window resets and capacity is back
+    #[serde(default)]
+    pub notify_on_reset: bool,
+    /// Further rate limit levels (0-100) notified after the window threshold
+    #[serde(default = "default_escalation_levels")]
+    pub escalation_levels: Vec<u8>,
+    /// Percentage points usage must drop below a level before it can fire again
+    #[serde(default = "default_hysteresis_percent")]
+    pub hysteresis_percent: u8,
+    /// Sinks for this account (None = use the global sinks)
+    #[serde(default)]
+    pub sinks: Option<Vec<NotificationSinkConfig>>,
+    /// Quiet hours for this account (None = use the global quiet hours)
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    /// Custom title/body per event type (e.g. "usage_level", "credits_low")
+    #[serde(default)]
+    pub templates: BTreeMap<String, NotificationTemplate>,
+    /// Consecutive failures of a health check before alerting (None = disabled)
+    #[serde(default = "default_health_failure_threshold")]
+    pub health_failure_threshold: Option<u8>,
+    /// Minimum minutes between repeated alerts for the same failing check
+    #[serde(default = "default_health_cooldown_minutes")]
+    pub health_cooldown_minutes: u32,
+}
+
+fn default_health_failure_threshold() -> Option<u8> {
+    Some(3)
+}
+
+fn default_health_cooldown_minutes() -> u32 {
+    360
+}
+
+/// Custom text for one notification event. Placeholders are written as
+/// `{{name}}`; `{{account}}` is always available, other names depend on the event.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NotificationTemplate {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+/// Per-account notification overrides. A missing field inherits the global
+/// default; for nullable settings an explicit `null` overrides it with "disabled".
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NotificationOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub primary_threshold: Option<Option<u8>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub secondary_threshold: Option<Option<u8>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub credits_threshold: Option<Option<u8>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub credits_balance_threshold: Option<Option<f64>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub credits_currency: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub credits_budget: Option<Option<f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_interval_minutes: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_on_reset: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation_levels: Option<Vec<u8>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hysteresis_percent: Option<u8>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sinks: Option<Option<Vec<NotificationSinkConfig>>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub quiet_hours: Option<Option<QuietHours>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates: Option<BTreeMap<String, NotificationTemplate>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_override",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub health_failure_threshold: Option<Option<u8>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_cooldown_minutes: Option<u32>,
+}
+
+/// Tell a present `null` (override with None) apart from a missing field (inherit)
+fn deserialize_override<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl NotificationOverrides {
+    /// Effective settings: overridden fields, everything else from `defaults`
+    pub fn resolve(&self, defaults: &NotificationSettings) -> NotificationSettings {
+        fn pick<T: Clone>(value: &Option<T>, default: &T) -> T {
+            value.clone().unwrap_or_else(|| default.clone())
+        }
+
+        NotificationSettings {
+            enabled: pick(&self.enabled, &defaults.enabled),
+            primary_threshold: pick(&self.primary_threshold, &defaults.primary_threshold),
+            secondary_threshold: pick(&self.secondary_threshold, &defaults.secondary_threshold),
+            credits_threshold: pick(&self.credits_threshold, &defaults.credits_threshold),
+            credits_balance_threshold: pick(
+                &self.credits_balance_threshold,
+                &defaults.credits_balance_threshold,
+            ),
+            credits_currency: pick(&self.credits_currency, &defaults.credits_currency),
+            credits_budget: pick(&self.credits_budget, &defaults.credits_budget),
+            min_interval_minutes: pick(&self.min_interval_minutes, &defaults.min_interval_minutes),
+            notify_on_reset: pick(&self.notify_on_reset, &defaults.notify_on_reset),
+            escalation_levels: pick(&self.escalation_levels, &defaults.escalation_levels),
+            hysteresis_percent: pick(&self.hysteresis_percent, &defaults.hysteresis_percent),
+            sinks: pick(&self.sinks, &defaults.sinks),
+            quiet_hours: pick(&self.quiet_hours, &defaults.quiet_hours),
+            templates: pick(&self.templates, &defaults.templates),
+            health_failure_threshold: pick(
+                &self.health_failure_threshold,
+                &defaults.health_failure_threshold,
+            ),
+            health_cooldown_minutes: pick(
+                &self.health_cooldown_minutes,
+                &defaults.health_cooldown_minutes,
+            ),
+        }
+    }
+
+    /// Overrides for the fields of `settings` that differ from `defaults`
+    pub fn diff(settings: &NotificationSettings, defaults: &NotificationSettings) -> Self {
+        fn differs<T: Clone + PartialEq>(value: &T, default: &T) -> Option<T> {
+            (value != default).then(|| value.clone())
+        }
+
+        Self {
+            enabled: differs(&settings.enabled, &defaults.enabled),
+            primary_threshold: differs(&settings.primary_threshold, &defaults.primary_threshold),
+            secondary_threshold: differs(
+                &settings.secondary_threshold,
+                &defaults.secondary_threshold,
+            ),
+            credits_threshold: differs(&settings.credits_threshold, &defaults.credits_threshold),
+            credits_balance_threshold: differs(
+                &settings.credits_balance_threshold,
+                &defaults.credits_balance_threshold,
+            ),
+            credits_currency: differs(&settings.credits_currency, &defaults.credits_currency),
+            credits_budget: differs(&settings.credits_budget, &defaults.credits_budget),
+            min_interval_minutes: differs(
+                &settings.min_interval_minutes,
+                &defaults.min_interval_minutes,
+            ),
+            notify_on_reset: differs(&settings.notify_on_reset, &defaults.notify_on_reset),
+            escalation_levels: differs(&settings.escalation_levels, &defaults.escalation_levels),
+            hysteresis_percent: differs(&settings.hysteresis_percent, &defaults.hysteresis_percent),
+            sinks: differs(&settings.sinks, &defaults.sinks),
+            quiet_hours: differs(&settings.quiet_hours, &defaults.quiet_hours),
+            templates: differs(&settings.templates, &defaults.templates),
+            health_failure_threshold: differs(
+                &settings.health_failure_threshold,
+                &defaults.health_failure_threshold,
+            ),
+            health_cooldown_minutes: differs(
+                &settings.health_cooldown_minutes,
+                &defaults.health_cooldown_minutes,
+            ),
+        }
+    }
+
+    /// Copy every field set in `other` over this one
+    pub fn merge(&mut self, other: &NotificationOverrides) {
+        fn overlay<T: Clone>(target: &mut Option<T>, value: &Option<T>) {
+            if value.is_some() {
+                target.clone_from(value);
+            }
+        }
+
+        overlay(&mut self.enabled, &other.enabled);
+        overlay(&mut self.primary_threshold, &other.primary_threshold);
+        overlay(&mut self.secondary_threshold, &other.secondary_threshold);
+        overlay(&mut self.credits_threshold, &other.credits_threshold);
+        overlay(
+            &mut self.credits_balance_threshold,
+            &other.credits_balance_threshold,
+        );
+        overlay(&mut self.credits_currency, &other.credits_currency);
+        overlay(&mut self.credits_budget, &other.credits_budget);
+        overlay(&mut self.min_interval_minutes, &other.min_interval_minutes);
+        overlay(&mut self.notify_on_reset, &other.notify_on_reset);
+        overlay(&mut self.escalation_levels, &other.escalation_levels);
+        overlay(&mut self.hysteresis_percent, &other.hysteresis_percent);
+        overlay(&mut self.sinks, &other.sinks);
+        overlay(&mut self.quiet_hours, &other.quiet_hours);
+        overlay(&mut self.templates, &other.templates);
+        overlay(
+            &mut self.health_failure_threshold,
+            &other.health_failure_threshold,
+        );
+        overlay(
+            &mut self.health_cooldown_minutes,
+            &other.health_cooldown_minutes,
+        );
+    }
+}
+
+/// A recurring do-not-disturb schedule. Notifications raised during quiet
+/// hours are queued and delivered as one digest when they end.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuietHours {
+    pub enabled: bool,
+    /// Local start time, "HH:MM"
+    pub start: String,
+    /// Local end time, "HH:MM"; earlier than `start` means it ends the next day
+    pub end: String,
+    /// Days quiet hours start on (0 = Monday ... 6 = Sunday); empty = every day
+    #[serde(default)]
+    pub weekdays: Vec<u8>,
+    /// IANA time zone of the schedule, e.g. "Europe/Berlin" (None = system time zone)
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    /// Deliver critical events (re-login needed, all accounts exhausted) immediately
+    #[serde(default = "default_allow_critical")]
+    pub allow_critical: bool,
+}
+
+fn default_allow_critical() -> bool {
+    true
+}
+
+fn default_escalation_levels() -> Vec<u8> {
+    vec![90, 100]
+}
+
+fn default_hysteresis_percent() -> u8 {
+    5
+}
+
+impl NotificationSettings {
+    /// Sorted notification levels for a window with the given threshold
+    pub fn levels_for(&self, threshold: Option<u8>) -> Vec<u8> {
+        let Some(threshold) = threshold else {
+            return Vec::new();
+        };
+        let mut levels: Vec<u8> = std::iter::once(threshold)
+            .chain(
+                self.escalation_levels
+                    .iter()
+                    .copied()
+                    .filter(|l| *l > threshold),
+            )
+            .collect();
+        levels.sort_unstable();
+        levels.dedup();
+        levels
+    }
 }
 
 impl Default for NotificationSettings {
@@ -74,17 +403,43 @@ impl Default for NotificationSettings {
             primary_threshold: Some(80),
             secondary_threshold: Some(80),
             credits_threshold: Some(20),
+            credits_balance_threshold: None,
+            credits_currency: None,
+            credits_budget: None,
             min_interval_minutes: 60,
+            notify_on_reset: false,
+            escalation_levels: default_escalation_levels(),
+            hysteresis_percent: default_hysteresis_percent(),
+            sinks: None,
+            quiet_hours: None,
+            templates: BTreeMap::new(),
+            health_failure_threshold: default_health_failure_threshold(),
+            health_cooldown_minutes: default_health_cooldown_minutes(),
         }
     }
 }
 
-/// Tracks last notification time per threshold to enforce min_interval
+/// Tracks last notification time per threshold and the escalation levels fired
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct LastNotifications {
     pub primary: Option<DateTime<Utc>>,
     pub secondary: Option<DateTime<Utc>>,
     pub credits: Option<DateTime<Utc>>,
+    /// Levels already notified in the current primary window
+    #[serde(default)]
+    pub primary_levels: WindowLevels,
+    /// Levels already notified in the current secondary window
+    #[serde(default)]
+    pub secondary_levels: WindowLevels,
+}
+
+/// Escalation levels fired during one rate limit window period
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct WindowLevels {
+    /// Reset time of the window the levels belong to
+    pub resets_at: Option<i64>,
+    /// Levels already notified, re-armed once usage drops below the hysteresis band
+    pub fired: Vec<u8>,
 }
 
 impl StoredAccount {
@@ -99,8 +454,12 @@ impl StoredAccount {
             auth_data: AuthData::ApiKey { key: api_key },
             created_at: Utc::now(),
             last_used_at: None,
-            notification_settings: NotificationSettings::default(),
+            notification_settings: NotificationOverrides::default(),
             last_notifications: LastNotifications::default(),
+            workspaces: Vec::new(),
+            workspace_id: None,
+            last_refresh_at: None,
+            refresh_token_issued_at: None,
         }
     }
 
@@ -128,10 +487,28 @@ impl StoredAccount {
             },
             created_at: Utc::now(),
             last_used_at: None,
-            notification_settings: NotificationSettings::default(),
+            notification_settings: NotificationOverrides::default(),
             last_notifications: LastNotifications::default(),
+            workspaces: Vec::new(),
+            workspace_id: None,
+            last_refresh_at: None,
+            refresh_token_issued_at: None,
         }
     }
+
+    /// Title of the pinned workspace, if known
+    pub fn workspace_title(&self) -> Option<String> {
+        let workspace_id = self.workspace_id.as_deref()?;
+        self.workspaces
+            .iter()
+            .find(|w| w.id == workspace_id)
+            .and_then(|w| w.title.clone())
+    }
+
+    /// A ChatGPT account whose tokens were revoked and cleared; it needs a new login
+    pub fn is_signed_out(&self) -> bool {
+        matches!(&self.auth_data, AuthData::ChatGPT { refresh_token, .. } if refresh_token.is_empty())
+    }
 }
 
 /// Authentication mode
@@ -166,6 +543,350 @@ pub enum AuthData {
     },
 }
 
+// ============================================================================
+// App-wide settings (settings.json)
+// ============================================================================
+
+/// App-wide settings stored next to accounts.json
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AppSettings {
+    /// Overrides for OAuth and API endpoints
+    #[serde(default)]
+    pub endpoints: EndpointSettings,
+    /// OAuth login behaviour
+    #[serde(default)]
+    pub login: LoginSettings,
+    /// Shared HTTP client configuration
+    #[serde(default)]
+    pub http: HttpSettings,
+    /// Usage fetching behaviour
+    #[serde(default)]
+    pub usage: UsageSettings,
+    /// Notification delivery shared by all accounts
+    #[serde(default)]
+    pub notifications: GlobalNotificationSettings,
+    /// Handling of IDE app-server processes when switching accounts
+    #[serde(default)]
+    pub processes: ProcessSettings,
+}
+
+/// HTTP client settings shared by all outgoing requests
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HttpSettings {
+    /// Total time allowed for a single request
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Time allowed to establish a connection
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// How long idle pooled connections are kept alive
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+    /// Proxy URL for all requests (e.g. "http://127.0.0.1:8080").
+    /// When unset, the standard proxy environment variables are used.
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+fn default_request_timeout_secs() -> u64 {
+    30
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_pool_idle_timeout_secs() -> u64 {
+    90
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            request_timeout_secs: default_request_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
+            proxy: None,
+        }
+    }
+}
+
+/// Usage fetching settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSettings {
+    /// Maximum number of accounts fetched at the same time
+    #[serde(default = "default_refresh_concurrency")]
+    pub refresh_concurrency: usize,
+    /// How long fetched usage is served from cache before hitting the API again
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    /// Keep the last usage per account in usage_cache.json across restarts
+    #[serde(default)]
+    pub persist_cache: bool,
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    60
+}
+
+fn default_refresh_concurrency() -> usize {
+    4
+}
+
+impl Default for UsageSettings {
+    fn default() -> Self {
+        Self {
+            refresh_concurrency: default_refresh_concurrency(),
+            cache_ttl_secs: default_cache_ttl_secs(),
+            persist_cache: false,
+        }
+    }
+}
+
+/// What to do with IDE app-servers (Antigravity, VS Code) after switching accounts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AppServerRestartPolicy {
+    /// Leave them running with the previous credentials
+    Never,
+    /// Report them so the user can confirm the restart
+    #[default]
+    Ask,
+    /// Restart them right after switching
+    Always,
+}
+
+/// IDE app-server process settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessSettings {
+    #[serde(default)]
+    pub app_server_restart: AppServerRestartPolicy,
+    /// Seconds to wait for an app-server to exit after SIGTERM before sending SIGKILL
+    #[serde(default = "default_terminate_timeout_secs")]
+    pub terminate_timeout_secs: u64,
+}
+
+fn default_terminate_timeout_secs() -> u64 {
+    5
+}
+
+impl Default for ProcessSettings {
+    fn default() -> Self {
+        Self {
+            app_server_restart: AppServerRestartPolicy::default(),
+            terminate_timeout_secs: default_terminate_timeout_secs(),
+        }
+    }
+}
+
+/// Outcome of restarting IDE app-server processes
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AppServerRestartResult {
+    /// Processes that exited; the IDE extension starts replacements
+    pub restarted_pids: Vec<u32>,
+    /// Those of `restarted_pids` that ignored SIGTERM and were killed
+    pub forced_pids: Vec<u32>,
+    /// Processes still running after SIGKILL
+    pub failed_pids: Vec<u32>,
+}
+
+/// Result of switching accounts
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SwitchAccountResult {
+    /// App-servers awaiting confirmation under the "ask" policy
+    pub pending_restart_pids: Vec<u32>,
+    /// What happened to the app-servers under the "always" policy
+    pub restart: Option<AppServerRestartResult>,
+}
+
+/// Notification delivery settings shared by all accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalNotificationSettings {
+    /// Notification settings accounts inherit unless they override a field.
+    /// Unset `sinks` and `quiet_hours` here fall back to the fields below.
+    #[serde(default)]
+    pub defaults: NotificationSettings,
+    /// Where notifications go unless an account overrides it
+    #[serde(default = "default_notification_sinks")]
+    pub sinks: Vec<NotificationSinkConfig>,
+    /// Extra attempts for a failed delivery, with exponential backoff
+    #[serde(default = "default_delivery_retries")]
+    pub delivery_retries: u8,
+    /// Quiet hours unless an account overrides them
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    /// Days notification history entries are kept
+    #[serde(default = "default_history_retention_days")]
+    pub history_retention_days: u32,
+    /// Maximum number of notification history entries kept
+    #[serde(default = "default_history_max_entries")]
+    pub history_max_entries: usize,
+}
+
+fn default_history_retention_days() -> u32 {
+    30
+}
+
+fn default_history_max_entries() -> usize {
+    1000
+}
+
+fn default_notification_sinks() -> Vec<NotificationSinkConfig> {
+    vec![NotificationSinkConfig::Desktop]
+}
+
+fn default_delivery_retries() -> u8 {
+    2
+}
+
+impl Default for GlobalNotificationSettings {
+    fn default() -> Self {
+        Self {
+            defaults: NotificationSettings::default(),
+            sinks: default_notification_sinks(),
+            delivery_retries: default_delivery_retries(),
+            quiet_hours: None,
+            history_retention_days: default_history_retention_days(),
+            history_max_entries: default_history_max_entries(),
+        }
+    }
+}
+
+/// A destination for notifications
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationSinkConfig {
+    /// OS native notification on the machine running the app
+    Desktop,
+    /// JSON POST to an arbitrary URL
+    Webhook {
+        url: String,
+        /// JSON body with `{{title}}`, `{{body}}`, `{{account}}`, `{{event}}` and
+        /// `{{timestamp}}` placeholders; a default body is used when unset
+        #[serde(default)]
+        body_template: Option<String>,
+        /// Extra request headers (e.g. an authorization token)
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+    /// Slack incoming webhook
+    Slack { webhook_url: String },
+    /// Discord channel webhook
+    Discord { webhook_url: String },
+}
+
+/// Operation whose repeated failure raises a health notification
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthCheck {
+    TokenRefresh,
+    UsageFetch,
+    Warmup,
+}
+
+/// A failing health check of an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountHealthIssue {
+    pub account_id: String,
+    pub check: HealthCheck,
+    pub consecutive_failures: u32,
+    pub last_error: String,
+    pub first_failed_at: DateTime<Utc>,
+    /// When the last alert was sent; None until the failure threshold is reached
+    pub alerted_at: Option<DateTime<Utc>>,
+}
+
+/// Overall outcome of a notification
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationOutcome {
+    /// Every sink accepted it
+    Delivered,
+    /// Some sinks failed
+    PartiallyDelivered,
+    /// No sink accepted it
+    Failed,
+    /// Held back by quiet hours for the digest
+    Queued,
+}
+
+/// A notification recorded in notification_history.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationHistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    /// Missing for entries recorded before accounts were tracked by ID
+    #[serde(default)]
+    pub account_id: Option<String>,
+    /// Account name when the notification was sent
+    pub account_name: String,
+    /// Event type, e.g. "usage_level", "credits_low" or "digest"
+    pub event: String,
+    pub title: String,
+    pub body: String,
+    /// The value that triggered it, e.g. used percent or credits balance
+    pub value: Option<f64>,
+    pub critical: bool,
+    pub outcome: NotificationOutcome,
+    /// Result per sink; empty while queued
+    pub deliveries: Vec<SinkDeliveryResult>,
+}
+
+/// Outcome of delivering a notification to one sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkDeliveryResult {
+    /// Sink type, e.g. "desktop" or "slack"
+    pub sink: String,
+    pub success: bool,
+    pub attempts: u8,
+    pub error: Option<String>,
+}
+
+/// OAuth login settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginSettings {
+    /// How long the local callback server waits for the browser redirect
+    #[serde(default = "default_callback_timeout_secs")]
+    pub callback_timeout_secs: u64,
+}
+
+fn default_callback_timeout_secs() -> u64 {
+    300
+}
+
+impl Default for LoginSettings {
+    fn default() -> Self {
+        Self {
+            callback_timeout_secs: default_callback_timeout_secs(),
+        }
+    }
+}
+
+/// Endpoint overrides; unset fields fall back to the official endpoints
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct EndpointSettings {
+    /// OAuth issuer base URL (e.g. "https://auth.openai.com")
+    #[serde(default)]
+    pub issuer: Option<String>,
+    /// OAuth client ID
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// ChatGPT backend API base URL (e.g. "https://chatgpt.com/backend-api")
+    #[serde(default)]
+    pub chatgpt_backend_api: Option<String>,
+    /// OpenAI API base URL (e.g. "https://api.openai.com/v1")
+    #[serde(default)]
+    pub openai_api: Option<String>,
+}
+
+/// Effective endpoint configuration after applying settings and environment overrides
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EndpointConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub chatgpt_backend_api: String,
+    pub openai_api: String,
+}
+
 // ============================================================================
 // Types for Codex's auth.json format (for compatibility)
 // ============================================================================
@@ -213,10 +934,41 @@ pub struct AccountInfo {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub last_used_at: Option<DateTime<Utc>>,
+    /// Workspace this account is pinned to
+    pub workspace_id: Option<String>,
+    pub workspace_title: Option<String>,
+    /// Number of workspaces available to this login
+    pub workspace_count: usize,
+    /// When the stored access token expires (ChatGPT auth)
+    pub access_token_expires_at: Option<DateTime<Utc>>,
+    /// When the tokens were last refreshed
+    pub last_refresh_at: Option<DateTime<Utc>>,
+    /// Age of the refresh token in seconds
+    pub refresh_token_age_secs: Option<i64>,
+    /// End of the current paid subscription period, if the ID token carries it
+    pub subscription_active_until: Option<DateTime<Utc>>,
+    /// Tokens were revoked at logout; the account needs a new login
+    pub signed_out: bool,
 }
 
 impl AccountInfo {
+    /// Account info for the frontend, with token expiry and age read from the claims
     pub fn from_stored(account: &StoredAccount, active_id: Option<&str>) -> Self {
+        let times = match &account.auth_data {
+            AuthData::ChatGPT {
+                id_token,
+                access_token,
+                ..
+            } => TokenTimes::from_tokens(id_token, access_token),
+            AuthData::ApiKey { .. } => TokenTimes::default(),
+        };
+
+        // Accounts stored before these were tracked fall back to the token claims
+        let last_refresh_at = account.last_refresh_at.or(times.access_token_issued_at);
+        let refresh_token_issued_at = account
+            .refresh_token_issued_at
+            .or(times.refresh_token_issued_at);
+
         Self {
             id: account.id.clone(),
             name: account.name.clone(),
@@ -226,6 +978,15 @@ impl AccountInfo {
             is_active: active_id == Some(&account.id),
             created_at: account.created_at,
             last_used_at: account.last_used_at,
+            workspace_id: account.workspace_id.clone(),
+            workspace_title: account.workspace_title(),
+            workspace_count: account.workspaces.len(),
+            access_token_expires_at: times.access_token_expires_at,
+            last_refresh_at,
+            refresh_token_age_secs: refresh_token_issued_at
+                .map(|issued| (Utc::now() - issued).num_seconds().max(0)),
+            subscription_active_until: times.subscription_active_until,
+            signed_out: account.is_signed_out(),
         }
     }
 }
@@ -257,10 +1018,178 @@ pub struct UsageInfo {
     pub credits_balance: Option<String>,
     /// Error message if usage fetch failed
     pub error: Option<String>,
+    /// Structured form of `error`
+    #[serde(default)]
+    pub error_detail: Option<UsageError>,
+    /// Spend and token usage (API key accounts)
+    #[serde(default)]
+    pub api_key_usage: Option<ApiKeyUsage>,
+    /// Rate limits from the response headers of the latest request
+    #[serde(default)]
+    pub rate_limit_snapshot: Option<RateLimitSnapshot>,
+    /// Windows other than primary/secondary reported by the backend
+    #[serde(default)]
+    pub additional_windows: Vec<UsageWindow>,
+    /// Credit fields not mapped to the fields above
+    #[serde(default)]
+    pub credits_extra: BTreeMap<String, serde_json::Value>,
+    /// The usage payload as received, kept for debugging
+    #[serde(default)]
+    pub raw_payload: Option<serde_json::Value>,
+    /// When the usage data was fetched from the API
+    #[serde(default)]
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// Whether the data was just fetched, served from cache, or kept after a failed fetch
+    #[serde(default)]
+    pub freshness: UsageFreshness,
+}
+
+/// A named usage window beyond primary/secondary
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageWindow {
+    pub name: String,
+    /// Usage percentage (0-100)
+    pub used_percent: f64,
+    pub window_minutes: Option<i64>,
+    /// Reset timestamp (unix seconds)
+    pub resets_at: Option<i64>,
+}
+
+/// Rate limits parsed from `x-codex-*` and `x-ratelimit-*` response headers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RateLimitSnapshot {
+    pub captured_at: DateTime<Utc>,
+    /// Codex primary window (`x-codex-primary-*`)
+    pub primary: Option<SnapshotWindow>,
+    /// Codex secondary window (`x-codex-secondary-*`)
+    pub secondary: Option<SnapshotWindow>,
+    pub has_credits: Option<bool>,
+    pub unlimited_credits: Option<bool>,
+    pub credits_balance: Option<String>,
+    /// OpenAI API request limit (`x-ratelimit-*-requests`)
+    pub requests: Option<HeaderLimit>,
+    /// OpenAI API token limit (`x-ratelimit-*-tokens`)
+    pub tokens: Option<HeaderLimit>,
+}
+
+/// A Codex usage window reported in headers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotWindow {
+    pub used_percent: f64,
+    pub window_minutes: Option<i64>,
+    /// Reset timestamp (unix seconds)
+    pub resets_at: Option<i64>,
+}
+
+/// A limit/remaining pair reported in `x-ratelimit-*` headers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HeaderLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Reset timestamp (unix seconds)
+    pub resets_at: Option<i64>,
+}
+
+impl HeaderLimit {
+    /// Share of the limit already used (percentage 0-100)
+    pub fn used_percent(&self) -> Option<f64> {
+        match (self.limit, self.remaining) {
+            (Some(limit), Some(remaining)) if limit > 0 => {
+                let used = limit.saturating_sub(remaining) as f64;
+                Some((used / limit as f64 * 100.0).clamp(0.0, 100.0))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Organization spend and token usage for an API key, for the current calendar month
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ApiKeyUsage {
+    /// Start of the reporting period (first day of the month, UTC)
+    pub period_start: Option<DateTime<Utc>>,
+    pub input_tokens: Option<u64>,
+    pub cached_input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub requests: Option<u64>,
+    /// Total cost over the period
+    pub cost: Option<f64>,
+    /// Currency of `cost` (e.g. "usd")
+    pub currency: Option<String>,
+    /// Whether the key could read the organization usage and costs endpoints
+    /// (requires an admin key); when false only rate limits are reported
+    pub organization_access: bool,
+}
+
+/// Category of a failed usage fetch
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageErrorKind {
+    /// The refresh token was rejected by the issuer (re-login needed)
+    LoginRequired,
+    /// The usage endpoint rejected the credentials even after a token refresh
+    Unauthorized,
+    /// Authenticated but not allowed, e.g. the plan is not eligible
+    Forbidden,
+    /// Too many requests; see `retry_after_secs`
+    RateLimited,
+    /// The API returned a 5xx status
+    ServerError,
+    /// The request could not be sent or timed out
+    Network,
+    /// The response could not be understood
+    Parse,
+    /// Usage cannot be fetched for this account (signed out)
+    Unsupported,
+    /// Any other failure
+    Other,
+}
+
+/// Structured usage fetch error with retry hints
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageError {
+    pub kind: UsageErrorKind,
+    pub message: String,
+    /// HTTP status, when the API answered
+    pub status: Option<u16>,
+    /// Seconds to wait before retrying, from the Retry-After header
+    pub retry_after_secs: Option<u64>,
+    /// Whether retrying later may succeed without user action
+    pub retryable: bool,
+}
+
+impl UsageError {
+    pub fn new(kind: UsageErrorKind, message: impl Into<String>) -> Self {
+        let retryable = matches!(
+            kind,
+            UsageErrorKind::RateLimited | UsageErrorKind::ServerError | UsageErrorKind::Network
+        );
+        Self {
+            kind,
+            message: message.into(),
+            status: None,
+            retry_after_secs: None,
+            retryable,
+        }
+    }
+}
+
+/// Where a `UsageInfo` came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageFreshness {
+    /// Fetched from the API by this request
+    #[default]
+    Fresh,
+    /// Served from the cache because it is younger than the TTL
+    Cached,
+    /// Last good data, returned because the latest fetch failed (see `error`)
+    StaleAfterError,
 }
 
 impl UsageInfo {
-    pub fn error(account_id: String, error: String) -> Self {
+    /// Usage without any data, fetched now
+    pub fn new(account_id: String) -> Self {
         Self {
             account_id,
             plan_type: None,
@@ -273,11 +1202,87 @@ impl UsageInfo {
             has_credits: None,
             unlimited_credits: None,
             credits_balance: None,
+            error: None,
+            error_detail: None,
+            api_key_usage: None,
+            rate_limit_snapshot: None,
+            additional_windows: Vec::new(),
+            credits_extra: BTreeMap::new(),
+            raw_payload: None,
+            fetched_at: Some(Utc::now()),
+            freshness: UsageFreshness::Fresh,
+        }
+    }
+
+    pub fn error(account_id: String, error: String) -> Self {
+        Self {
             error: Some(error),
+            ..Self::new(account_id)
+        }
+    }
+
+    /// Usage result for a failed fetch, keeping `error` as the readable message
+    pub fn failed(account_id: String, error: UsageError) -> Self {
+        Self {
+            error_detail: Some(error.clone()),
+            ..Self::error(account_id, error.message)
         }
     }
 }
 
+/// Usage aggregated across all accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub total_accounts: usize,
+    /// Accounts with at least one usage window
+    pub accounts_with_data: usize,
+    /// Accounts with any window at 100%
+    pub exhausted_accounts: usize,
+    /// Accounts usable now or whose exhausted windows all reset within the hour
+    pub usable_next_hour_accounts: usize,
+    pub primary: WindowSummary,
+    pub secondary: WindowSummary,
+    /// Earliest reset across all accounts and windows (unix seconds)
+    pub next_reset_at: Option<i64>,
+    /// Sums of the numeric credit balances, one per currency
+    pub credits_totals: Vec<CreditsTotal>,
+    pub unlimited_credit_accounts: usize,
+    pub plans: Vec<PlanUsageSummary>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Credit balances of one currency summed across accounts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CreditsTotal {
+    /// ISO 4217 code; None when the balances carried no currency
+    pub currency: Option<String>,
+    pub amount: f64,
+    pub accounts: usize,
+}
+
+/// Aggregate of one usage window across accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSummary {
+    /// Accounts reporting this window
+    pub accounts: usize,
+    /// Sum of the remaining percentage over those accounts (e.g. 250 = two and a half accounts)
+    pub remaining_percent_total: f64,
+    pub average_used_percent: Option<f64>,
+    pub exhausted_accounts: usize,
+    /// Earliest reset of this window (unix seconds)
+    pub next_reset_at: Option<i64>,
+}
+
+/// Usage aggregate for one plan type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanUsageSummary {
+    pub plan_type: String,
+    pub accounts: usize,
+    pub exhausted_accounts: usize,
+    pub primary_remaining_percent: f64,
+    pub secondary_remaining_percent: f64,
+}
+
 /// Warm-up execution summary across accounts
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WarmupSummary {
@@ -300,46 +1305,187 @@ pub struct ImportAccountsSummary {
     pub skipped_count: usize,
 }
 
+/// Outcome of revoking an account's tokens at the issuer
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RevocationResult {
+    /// Whether revocation was requested
+    pub requested: bool,
+    /// Whether the issuer accepted the revocation
+    pub revoked: bool,
+    /// Why revocation failed or was not possible
+    pub error: Option<String>,
+}
+
 /// OAuth login information returned to frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthLoginInfo {
+    /// Login session ID used to complete or cancel this login
+    #[serde(default)]
+    pub session_id: String,
     /// The authorization URL to open in browser
     pub auth_url: String,
     /// The local callback port
     pub callback_port: u16,
 }
 
+/// Device authorization login information returned to frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceLoginInfo {
+    /// Login session ID used to complete or cancel this login
+    #[serde(default)]
+    pub session_id: String,
+    /// Page where the user enters the code
+    pub verification_uri: String,
+    /// Verification page with the code already filled in, if provided
+    pub verification_uri_complete: Option<String>,
+    /// Code the user has to enter on the verification page
+    pub user_code: String,
+    /// Seconds until the code expires
+    pub expires_in: u64,
+    /// Polling interval in seconds
+    pub interval: u64,
+}
+
+/// How a pending login is completed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFlow {
+    /// Browser redirect to the local callback server
+    Callback,
+    /// Device authorization code entered on any device
+    Device,
+    /// Redirect URL pasted back by the user
+    Manual,
+}
+
+/// Progress stages of a login flow, emitted to the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthProgressStage {
+    /// Callback server is listening for the browser redirect
+    Listening,
+    /// The authorization URL was opened in the default browser
+    BrowserOpened,
+    /// Device code issued, waiting for the user to approve it
+    WaitingForApproval,
+    /// The browser redirect reached the callback server
+    CallbackReceived,
+    /// Exchanging the authorization code for tokens
+    Exchanging,
+    Completed,
+    Failed,
+    Cancelled,
+    TimedOut,
+}
+
+/// Payload of the `oauth-login-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthProgressEvent {
+    pub session_id: String,
+    pub stage: OAuthProgressStage,
+    /// Error details for the `failed` stage
+    pub message: Option<String>,
+}
+
+/// A login session that has been started but not completed yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingLoginInfo {
+    pub session_id: String,
+    pub account_name: String,
+    pub flow: LoginFlow,
+    /// Local callback port, for callback logins
+    pub callback_port: Option<u16>,
+    pub started_at: DateTime<Utc>,
+}
+
 // ============================================================================
 // API Response types (from Codex backend)
 // ============================================================================
 
-/// Rate limit status from API
+/// Rate limit status from API.
+/// Every field is optional so that backend additions or removals degrade
+/// to missing data instead of a parse failure.
 #[derive(Debug, Clone, Deserialize)]
 pub struct RateLimitStatusPayload {
-    pub plan_type: String,
+    #[serde(default)]
+    pub plan_type: Option<String>,
     #[serde(default)]
     pub rate_limit: Option<RateLimitDetails>,
     #[serde(default)]
     pub credits: Option<CreditStatusDetails>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Rate limit fields keyed by name ("primary_window", "secondary_window", or
+/// any window the backend adds later, plus flags such as "limit_reached")
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct RateLimitDetails {
-    pub primary_window: Option<RateLimitWindow>,
-    pub secondary_window: Option<RateLimitWindow>,
+    #[serde(flatten)]
+    pub fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl RateLimitDetails {
+    /// Parse one named window; malformed windows are skipped
+    pub fn window(&self, name: &str) -> Option<RateLimitWindow> {
+        let value = self.fields.get(name)?;
+        serde_json::from_value::<RateLimitWindow>(value.clone())
+            .ok()
+            .filter(|w| w.used_percent.is_some())
+    }
+
+    /// All windows, in name order
+    pub fn windows(&self) -> Vec<(String, RateLimitWindow)> {
+        self.fields
+            .keys()
+            .filter_map(|name| Some((name.clone(), self.window(name)?)))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct RateLimitWindow {
-    pub used_percent: f64,
-    pub limit_window_seconds: Option<i32>,
+    #[serde(default)]
+    pub used_percent: Option<f64>,
+    #[serde(default)]
+    pub limit_window_seconds: Option<i64>,
+    #[serde(default)]
     pub reset_at: Option<i64>,
+    #[serde(default)]
+    pub reset_after_seconds: Option<i64>,
+}
+
+impl RateLimitWindow {
+    pub fn window_minutes(&self) -> Option<i64> {
+        self.limit_window_seconds.map(|s| (s + 59) / 60)
+    }
+
+    pub fn resets_at(&self) -> Option<i64> {
+        self.reset_at.or_else(|| {
+            self.reset_after_seconds
+                .map(|delay| Utc::now().timestamp() + delay)
+        })
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct CreditStatusDetails {
-    pub has_credits: bool,
-    pub unlimited: bool,
     #[serde(default)]
-    pub balance: Option<String>,
+    pub has_credits: Option<bool>,
+    #[serde(default)]
+    pub unlimited: Option<bool>,
+    /// Usually a string, but accept a number too
+    #[serde(default)]
+    pub balance: Option<serde_json::Value>,
+    /// Credit fields not modelled above
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl CreditStatusDetails {
+    pub fn balance_text(&self) -> Option<String> {
+        match self.balance.as_ref()? {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
 }
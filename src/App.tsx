@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback, useMemo, useRef } from "react";
 import { invoke, isTauri } from "@tauri-apps/api/core";
-import { open, save } from "@tauri-apps/plugin-dialog";
+import { ask, open, save } from "@tauri-apps/plugin-dialog";
 import { useAccounts } from "./hooks/useAccounts";
 import { AccountCard, AddAccountModal, ThemeToggle } from "./components";
 import type { CodexProcessInfo } from "./types";
@@ -16,6 +16,7 @@ function App() {
     warmupAccount,
     warmupAllAccounts,
     switchAccount,
+    restartIdeAppServers,
     deleteAccount,
     renameAccount,
     importFromFile,
@@ -25,6 +26,9 @@ function App() {
     importAccountsFullEncryptedFile,
     startOAuthLogin,
     completeOAuthLogin,
+    startManualLogin,
+    completeManualLogin,
+    startDeviceLogin,
     cancelOAuthLogin,
     getNotificationSettings,
     updateNotificationSettings,
@@ -128,7 +132,17 @@ function App() {
 
     try {
       setSwitchingId(accountId);
-      await switchAccount(accountId);
+      const result = await switchAccount(accountId);
+      const pending = result.pending_restart_pids;
+      if (pending.length > 0) {
+        const confirmed = await ask(
+          `${pending.length} IDE Codex app-server process(es) still use the previous account. Restart them now?`,
+          { title: "Restart IDE app-servers", kind: "warning" }
+        );
+        if (confirmed) {
+          await restartIdeAppServers(pending);
+        }
+      }
     } catch (err) {
       console.error("Failed to switch account:", err);
     } finally {
@@ -694,6 +708,9 @@ function App() {
         onImportFile={importFromFile}
         onStartOAuth={startOAuthLogin}
         onCompleteOAuth={completeOAuthLogin}
+        onStartManual={startManualLogin}
+        onCompleteManual={completeManualLogin}
+        onStartDevice={startDeviceLogin}
         onCancelOAuth={cancelOAuthLogin}
       />
 
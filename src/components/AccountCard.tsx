@@ -224,15 +224,27 @@ export function AccountCard({
         ) : (
           <button
             onClick={onSwitch}
-            disabled={switching || switchDisabled}
+            disabled={switching || switchDisabled || account.signed_out}
             className={`flex-1 px-4 py-2 text-sm font-medium rounded-lg transition-colors disabled:opacity-50 ${
-              switchDisabled
+              switchDisabled || account.signed_out
                 ? "bg-gray-200 dark:bg-gray-700 text-gray-400 dark:text-gray-500 cursor-not-allowed"
                 : "bg-gray-900 dark:bg-gray-100 hover:bg-gray-800 dark:hover:bg-white text-white dark:text-gray-900"
             }`}
-            title={switchDisabled ? "Close all Codex processes first" : undefined}
+            title={
+              account.signed_out
+                ? "Signed out; log in again to use this account"
+                : switchDisabled
+                  ? "Close all Codex processes first"
+                  : undefined
+            }
           >
-            {switching ? "Switching..." : switchDisabled ? "Codex Running" : "Switch"}
+            {account.signed_out
+              ? "Signed Out"
+              : switching
+                ? "Switching..."
+                : switchDisabled
+                  ? "Codex Running"
+                  : "Switch"}
           </button>
         )}
         <button
@@ -1,24 +1,93 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
+import { isTauri } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { openUrl } from "@tauri-apps/plugin-opener";
 import { open } from "@tauri-apps/plugin-dialog";
+import type {
+  DeviceLoginInfo,
+  OAuthLoginInfo,
+  OAuthProgressEvent,
+  OAuthProgressStage,
+} from "../types";
 
 interface AddAccountModalProps {
   isOpen: boolean;
   onClose: () => void;
   onImportFile: (path: string, name: string) => Promise<void>;
-  onStartOAuth: (name: string) => Promise<{ auth_url: string }>;
-  onCompleteOAuth: () => Promise<unknown>;
-  onCancelOAuth: () => Promise<void>;
+  onStartOAuth: (name: string) => Promise<OAuthLoginInfo>;
+  onCompleteOAuth: (sessionId?: string) => Promise<unknown>;
+  onStartManual: (name: string) => Promise<OAuthLoginInfo>;
+  onCompleteManual: (callback: string, sessionId: string) => Promise<unknown>;
+  onStartDevice: (name: string) => Promise<DeviceLoginInfo>;
+  onCancelOAuth: (sessionId?: string) => Promise<void>;
 }
 
 type Tab = "oauth" | "import";
 
+/** How the ChatGPT login is completed */
+type LoginMethod = "browser" | "paste" | "device";
+
+const LOGIN_METHODS: { id: LoginMethod; label: string }[] = [
+  { id: "browser", label: "Browser" },
+  { id: "paste", label: "Paste URL" },
+  { id: "device", label: "Device Code" },
+];
+
+/** Headline shown while a login is in progress */
+const PROGRESS_LABELS: Partial<Record<OAuthProgressStage, string>> = {
+  listening: "Waiting for browser login...",
+  browser_opened: "Opened your browser, waiting for login...",
+  waiting_for_approval: "Waiting for approval...",
+  callback_received: "Login received, finishing up...",
+  exchanging: "Exchanging authorization code...",
+  completed: "Login complete",
+};
+
+/** Read-only link with Copy and Open buttons */
+function LinkBox({ url }: { url: string }) {
+  const [copied, setCopied] = useState<boolean>(false);
+
+  return (
+    <div className="flex items-center gap-2 mb-2 bg-gray-50 p-2 rounded-lg border border-gray-200">
+      <input
+        type="text"
+        readOnly
+        value={url}
+        className="flex-1 bg-transparent border-none text-xs text-gray-600 focus:outline-none focus:ring-0 truncate"
+      />
+      <button
+        onClick={() => {
+          navigator.clipboard.writeText(url);
+          setCopied(true);
+          setTimeout(() => setCopied(false), 2000);
+        }}
+        className={`px-3 py-1.5 border rounded text-xs font-medium transition-colors shrink-0 
+          ${copied
+            ? "bg-green-50 border-green-200 text-green-700"
+            : "bg-white border-gray-200 text-gray-700 hover:bg-gray-50"
+          }`}
+      >
+        {copied ? "Copied!" : "Copy"}
+      </button>
+      <button
+        onClick={() => openUrl(url)}
+        className="px-3 py-1.5 bg-gray-900 border border-gray-900 rounded text-xs font-medium text-white hover:bg-gray-800 transition-colors shrink-0"
+      >
+        Open
+      </button>
+    </div>
+  );
+}
+
 export function AddAccountModal({
   isOpen,
   onClose,
   onImportFile,
   onStartOAuth,
   onCompleteOAuth,
+  onStartManual,
+  onCompleteManual,
+  onStartDevice,
   onCancelOAuth,
 }: AddAccountModalProps) {
   const [activeTab, setActiveTab] = useState<Tab>("oauth");
@@ -27,23 +96,56 @@ export function AddAccountModal({
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [oauthPending, setOauthPending] = useState(false);
+  const [loginMethod, setLoginMethod] = useState<LoginMethod>("browser");
+  const [sessionId, setSessionId] = useState<string | null>(null);
   const [authUrl, setAuthUrl] = useState<string>("");
-  const [copied, setCopied] = useState<boolean>(false);
-  const isPrimaryDisabled = loading || (activeTab === "oauth" && oauthPending);
+  const [deviceInfo, setDeviceInfo] = useState<DeviceLoginInfo | null>(null);
+  const [pastedCallback, setPastedCallback] = useState("");
+  // Latest progress per session; events may arrive before the session ID is known
+  const [progress, setProgress] = useState<Record<string, OAuthProgressStage>>({});
+  const stage = sessionId ? progress[sessionId] : undefined;
+  const awaitingPaste = oauthPending && loginMethod === "paste";
+  const isPrimaryDisabled =
+    loading || (activeTab === "oauth" && oauthPending && !awaitingPaste);
+
+  useEffect(() => {
+    if (!isOpen || !isTauri()) return;
+    const unlisten = listen<OAuthProgressEvent>("oauth-login-progress", (event) => {
+      const { session_id, stage } = event.payload;
+      setProgress((prev) => ({ ...prev, [session_id]: stage }));
+    });
+    return () => {
+      void unlisten.then((stop) => stop());
+    };
+  }, [isOpen]);
+
+  const resetLogin = () => {
+    setProgress({});
+    setLoading(false);
+    setOauthPending(false);
+    setSessionId(null);
+    setAuthUrl("");
+    setDeviceInfo(null);
+    setPastedCallback("");
+  };
 
   const resetForm = () => {
     setName("");
     setFilePath("");
     setError(null);
-    setLoading(false);
-    setOauthPending(false);
-    setAuthUrl("");
+    resetLogin();
   };
 
-  const handleClose = () => {
+  const cancelPendingLogin = () => {
     if (oauthPending) {
-      onCancelOAuth();
+      void onCancelOAuth(sessionId ?? undefined).catch((err) => {
+        console.error("Failed to cancel login:", err);
+      });
     }
+  };
+
+  const handleClose = () => {
+    cancelPendingLogin();
     resetForm();
     onClose();
   };
@@ -57,18 +159,55 @@ export function AddAccountModal({
     try {
       setLoading(true);
       setError(null);
-      const info = await onStartOAuth(name.trim());
-      setAuthUrl(info.auth_url);
+
+      if (loginMethod === "paste") {
+        const info = await onStartManual(name.trim());
+        setSessionId(info.session_id);
+        setAuthUrl(info.auth_url);
+        setOauthPending(true);
+        setLoading(false);
+        return;
+      }
+
+      let started: string;
+      if (loginMethod === "device") {
+        const info = await onStartDevice(name.trim());
+        started = info.session_id;
+        setDeviceInfo(info);
+      } else {
+        const info = await onStartOAuth(name.trim());
+        started = info.session_id;
+        setAuthUrl(info.auth_url);
+      }
+      setSessionId(started);
       setOauthPending(true);
       setLoading(false);
 
       // Wait for completion
-      await onCompleteOAuth();
+      await onCompleteOAuth(started);
+      handleClose();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+      resetLogin();
+    }
+  };
+
+  const handleCompleteManual = async () => {
+    if (!sessionId) return;
+    if (!pastedCallback.trim()) {
+      setError("Please paste the URL from your browser's address bar");
+      return;
+    }
+
+    try {
+      setLoading(true);
+      setError(null);
+      await onCompleteManual(pastedCallback.trim(), sessionId);
       handleClose();
     } catch (err) {
+      // The login stays pending, so a corrected URL can be pasted
       setError(err instanceof Error ? err.message : String(err));
       setLoading(false);
-      setOauthPending(false);
     }
   };
 
@@ -137,11 +276,8 @@ export function AddAccountModal({
               key={tab}
               onClick={() => {
                 if (tab === "import" && oauthPending) {
-                  void onCancelOAuth().catch((err) => {
-                    console.error("Failed to cancel login:", err);
-                  });
-                  setOauthPending(false);
-                  setLoading(false);
+                  cancelPendingLogin();
+                  resetLogin();
                 }
                 setActiveTab(tab);
                 setError(null);
@@ -176,46 +312,88 @@ export function AddAccountModal({
           {/* Tab-specific content */}
           {activeTab === "oauth" && (
             <div className="text-sm text-gray-500 dark:text-gray-400">
-              {oauthPending ? (
-                <div className="text-center py-4">
-                  <div className="animate-spin h-8 w-8 border-2 border-gray-900 dark:border-gray-100 border-t-transparent dark:border-t-transparent rounded-full mx-auto mb-3"></div>
-                  <p className="text-gray-700 dark:text-gray-300 font-medium mb-2">Waiting for browser login...</p>
-                  <p className="text-xs text-gray-500 dark:text-gray-400 mb-4">
-                    Please open the following link in your browser to proceed:
-                  </p>
-                  <div className="flex items-center gap-2 mb-2 bg-gray-50 p-2 rounded-lg border border-gray-200">
-                    <input
-                      type="text"
-                      readOnly
-                      value={authUrl}
-                      className="flex-1 bg-transparent border-none text-xs text-gray-600 focus:outline-none focus:ring-0 truncate"
-                    />
+              {!oauthPending && (
+                <div className="flex gap-1 p-1 mb-3 bg-gray-100 dark:bg-gray-700 rounded-lg">
+                  {LOGIN_METHODS.map((method) => (
                     <button
+                      key={method.id}
                       onClick={() => {
-                        navigator.clipboard.writeText(authUrl);
-                        setCopied(true);
-                        setTimeout(() => setCopied(false), 2000);
+                        setLoginMethod(method.id);
+                        setError(null);
                       }}
-                      className={`px-3 py-1.5 border rounded text-xs font-medium transition-colors shrink-0 
-                        ${copied
-                          ? "bg-green-50 border-green-200 text-green-700"
-                          : "bg-white border-gray-200 text-gray-700 hover:bg-gray-50"
-                        }`}
+                      className={`flex-1 px-3 py-1.5 rounded-md text-xs font-medium transition-colors ${
+                        loginMethod === method.id
+                          ? "bg-white dark:bg-gray-800 text-gray-900 dark:text-gray-100 shadow-sm"
+                          : "text-gray-500 dark:text-gray-400 hover:text-gray-700 dark:hover:text-gray-300"
+                      }`}
                     >
-                      {copied ? "Copied!" : "Copy"}
+                      {method.label}
                     </button>
-                    <button
-                      onClick={() => openUrl(authUrl)}
-                      className="px-3 py-1.5 bg-gray-900 border border-gray-900 rounded text-xs font-medium text-white hover:bg-gray-800 transition-colors shrink-0"
-                    >
-                      Open
-                    </button>
-                  </div>
+                  ))}
                 </div>
-              ) : (
+              )}
+
+              {oauthPending && loginMethod === "browser" && (
+                <div className="text-center py-4">
+                  <div className="animate-spin h-8 w-8 border-2 border-gray-900 dark:border-gray-100 border-t-transparent dark:border-t-transparent rounded-full mx-auto mb-3"></div>
+                  <p className="text-gray-700 dark:text-gray-300 font-medium mb-2">
+                    {(stage && PROGRESS_LABELS[stage]) ?? "Waiting for browser login..."}
+                  </p>
+                  <p className="text-xs text-gray-500 dark:text-gray-400 mb-4">
+                    {stage === "browser_opened"
+                      ? "If no browser window appeared, open this link yourself:"
+                      : "Please open the following link in your browser to proceed:"}
+                  </p>
+                  <LinkBox url={authUrl} />
+                </div>
+              )}
+
+              {oauthPending && loginMethod === "paste" && (
+                <div className="py-2">
+                  <p className="text-xs text-gray-500 dark:text-gray-400 mb-2">
+                    1. Open this link in a browser on any machine and log in:
+                  </p>
+                  <LinkBox url={authUrl} />
+                  <p className="text-xs text-gray-500 dark:text-gray-400 mt-3 mb-2">
+                    2. The browser then fails to load a localhost page. Paste the full URL
+                    from its address bar here:
+                  </p>
+                  <textarea
+                    value={pastedCallback}
+                    onChange={(e) => setPastedCallback(e.target.value)}
+                    placeholder="http://localhost:1455/auth/callback?code=...&state=..."
+                    rows={3}
+                    className="w-full px-3 py-2 bg-white dark:bg-gray-700 border border-gray-200 dark:border-gray-600 rounded-lg text-xs font-mono text-gray-900 dark:text-gray-100 placeholder-gray-400 dark:placeholder-gray-500 focus:outline-none focus:border-gray-400 dark:focus:border-gray-500 focus:ring-1 focus:ring-gray-400 dark:focus:ring-gray-500 transition-colors"
+                  />
+                </div>
+              )}
+
+              {oauthPending && loginMethod === "device" && deviceInfo && (
+                <div className="text-center py-4">
+                  <p className="text-xs text-gray-500 dark:text-gray-400 mb-2">
+                    Open this page on any device and enter the code:
+                  </p>
+                  <LinkBox url={deviceInfo.verification_uri_complete ?? deviceInfo.verification_uri} />
+                  <p className="text-2xl font-mono font-semibold tracking-widest text-gray-900 dark:text-gray-100 my-3 select-all">
+                    {deviceInfo.user_code}
+                  </p>
+                  <div className="animate-spin h-6 w-6 border-2 border-gray-900 dark:border-gray-100 border-t-transparent dark:border-t-transparent rounded-full mx-auto mb-2"></div>
+                  <p className="text-xs text-gray-500 dark:text-gray-400">
+                    {stage && stage !== "waiting_for_approval"
+                      ? PROGRESS_LABELS[stage]
+                      : `Waiting for approval (code expires in ${Math.round(deviceInfo.expires_in / 60)} minutes)...`}
+                  </p>
+                </div>
+              )}
+
+              {!oauthPending && (
                 <p>
-                  Click the button below to generate a login link.
-                  You will need to open it in your browser to authenticate.
+                  {loginMethod === "browser" &&
+                    "Click the button below to generate a login link. You will need to open it in your browser to authenticate."}
+                  {loginMethod === "paste" &&
+                    "For machines whose browser cannot reach this app: log in anywhere, then paste the URL you were redirected to."}
+                  {loginMethod === "device" &&
+                    "Get a short code and approve it on any device, such as your phone."}
                 </p>
               )}
             </div>
@@ -260,15 +438,25 @@ export function AddAccountModal({
             Cancel
           </button>
           <button
-            onClick={activeTab === "oauth" ? handleOAuthLogin : handleImportFile}
+            onClick={
+              activeTab === "import"
+                ? handleImportFile
+                : awaitingPaste
+                  ? handleCompleteManual
+                  : handleOAuthLogin
+            }
             disabled={isPrimaryDisabled}
             className="flex-1 px-4 py-2.5 text-sm font-medium rounded-lg bg-gray-900 dark:bg-gray-100 hover:bg-gray-800 dark:hover:bg-white text-white dark:text-gray-900 transition-colors disabled:opacity-50"
           >
             {loading
               ? "Adding..."
-              : activeTab === "oauth"
-                ? "Generate Login Link"
-                : "Import"}
+              : activeTab === "import"
+                ? "Import"
+                : awaitingPaste
+                  ? "Finish Login"
+                  : loginMethod === "device"
+                    ? "Get Device Code"
+                    : "Generate Login Link"}
           </button>
         </div>
       </div>
@@ -25,7 +25,18 @@ export function NotificationSettings({
     primary_threshold: 80,
     secondary_threshold: 80,
     credits_threshold: 20,
+    credits_balance_threshold: null,
+    credits_currency: null,
+    credits_budget: null,
     min_interval_minutes: 60,
+    notify_on_reset: false,
+    escalation_levels: [90, 100],
+    hysteresis_percent: 5,
+    sinks: null,
+    quiet_hours: null,
+    templates: {},
+    health_failure_threshold: 3,
+    health_cooldown_minutes: 360,
   });
   const [loading, setLoading] = useState(false);
   const [saving, setSaving] = useState(false);
@@ -293,7 +304,7 @@ export function NotificationSettings({
                           </p>
                         ) : (
                           <p className="text-xs text-gray-500 dark:text-gray-400 mt-1">
-                            Notify when credits used exceeds threshold
+                            Percentage of your credits budget used
                           </p>
                         )}
                       </div>
@@ -320,9 +331,20 @@ export function NotificationSettings({
                           <span className="text-sm text-gray-500 dark:text-gray-400">minutes</span>
                         </div>
                         <p className="text-xs text-gray-500 dark:text-gray-400 mt-1">
-                          Prevents notification spam
+                          Cooldown between credits notifications
                         </p>
                       </div>
+
+                      {/* Reset Notifications */}
+                      <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300">
+                        <input
+                          type="checkbox"
+                          checked={settings.notify_on_reset}
+                          onChange={(e) => updateField("notify_on_reset", e.target.checked)}
+                          className="rounded border-gray-300 dark:border-gray-600 text-emerald-500 focus:ring-emerald-500"
+                        />
+                        Notify when an exhausted limit resets
+                      </label>
                     </>
                   )}
                 </>
@@ -7,6 +7,10 @@ import type {
   WarmupSummary,
   ImportAccountsSummary,
   NotificationSettings,
+  SwitchAccountResult,
+  AppServerRestartResult,
+  OAuthLoginInfo,
+  DeviceLoginInfo,
 } from "../types";
 
 export function useAccounts() {
@@ -103,8 +107,9 @@ export function useAccounts() {
   const switchAccount = useCallback(
     async (accountId: string) => {
       try {
-        await invoke("switch_account", { accountId });
+        const result = await invoke<SwitchAccountResult>("switch_account", { accountId });
         await loadAccounts(true); // Preserve usage data
+        return result;
       } catch (err) {
         throw err;
       }
@@ -112,6 +117,12 @@ export function useAccounts() {
     [loadAccounts]
   );
 
+  const restartIdeAppServers = useCallback(async (pids?: number[]) => {
+    return await invoke<AppServerRestartResult>("restart_ide_app_servers", {
+      pids: pids ?? null,
+    });
+  }, []);
+
   const deleteAccount = useCallback(
     async (accountId: string) => {
       try {
@@ -151,26 +162,65 @@ export function useAccounts() {
 
   const startOAuthLogin = useCallback(async (accountName: string) => {
     try {
-      const info = await invoke<{ auth_url: string; callback_port: number }>(
-        "start_login",
-        { accountName }
-      );
+      // The backend opens the browser and reports it with a `browser_opened` progress event
+      const info = await invoke<OAuthLoginInfo>("start_login", {
+        accountName,
+        openBrowser: true,
+      });
       return info;
     } catch (err) {
       throw err;
     }
   }, []);
 
-  const completeOAuthLogin = useCallback(async () => {
+  const completeOAuthLogin = useCallback(
+    async (sessionId?: string) => {
+      try {
+        const account = await invoke<AccountInfo>("complete_login", {
+          sessionId: sessionId ?? null,
+        });
+        await loadAccounts();
+        await refreshUsage();
+        return account;
+      } catch (err) {
+        throw err;
+      }
+    },
+    [loadAccounts, refreshUsage]
+  );
+
+  const startManualLogin = useCallback(async (accountName: string) => {
     try {
-      const account = await invoke<AccountInfo>("complete_login");
-      await loadAccounts();
-      await refreshUsage();
-      return account;
+      return await invoke<OAuthLoginInfo>("start_manual_login", { accountName });
     } catch (err) {
       throw err;
     }
-  }, [loadAccounts, refreshUsage]);
+  }, []);
+
+  const completeManualLogin = useCallback(
+    async (callback: string, sessionId: string) => {
+      try {
+        const account = await invoke<AccountInfo>("complete_manual_login", {
+          callback,
+          sessionId,
+        });
+        await loadAccounts();
+        await refreshUsage();
+        return account;
+      } catch (err) {
+        throw err;
+      }
+    },
+    [loadAccounts, refreshUsage]
+  );
+
+  const startDeviceLogin = useCallback(async (accountName: string) => {
+    try {
+      return await invoke<DeviceLoginInfo>("start_device_login", { accountName });
+    } catch (err) {
+      throw err;
+    }
+  }, []);
 
   const exportAccountsSlimText = useCallback(async () => {
     try {
@@ -224,9 +274,9 @@ export function useAccounts() {
     [loadAccounts, refreshUsage]
   );
 
-  const cancelOAuthLogin = useCallback(async () => {
+  const cancelOAuthLogin = useCallback(async (sessionId?: string) => {
     try {
-      await invoke("cancel_login");
+      await invoke("cancel_login", { sessionId: sessionId ?? null });
     } catch (err) {
       console.error("Failed to cancel login:", err);
     }
@@ -286,6 +336,7 @@ export function useAccounts() {
     warmupAccount,
     warmupAllAccounts,
     switchAccount,
+    restartIdeAppServers,
     deleteAccount,
     renameAccount,
     importFromFile,
@@ -295,6 +346,9 @@ export function useAccounts() {
     importAccountsFullEncryptedFile,
     startOAuthLogin,
     completeOAuthLogin,
+    startManualLogin,
+    completeManualLogin,
+    startDeviceLogin,
     cancelOAuthLogin,
     getNotificationSettings,
     updateNotificationSettings,
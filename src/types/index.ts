@@ -11,6 +11,21 @@ export interface AccountInfo {
   is_active: boolean;
   created_at: string;
   last_used_at: string | null;
+  workspace_id: string | null;
+  workspace_title: string | null;
+  workspace_count: number;
+  access_token_expires_at: string | null;
+  last_refresh_at: string | null;
+  refresh_token_age_secs: number | null;
+  subscription_active_until: string | null;
+  signed_out: boolean;
+}
+
+export interface AccountWorkspace {
+  id: string;
+  title: string | null;
+  role: string | null;
+  is_default: boolean;
 }
 
 export interface UsageInfo {
@@ -26,18 +41,162 @@ export interface UsageInfo {
   unlimited_credits: boolean | null;
   credits_balance: string | null;
   error: string | null;
+  error_detail: UsageError | null;
+  api_key_usage: ApiKeyUsage | null;
+  rate_limit_snapshot: RateLimitSnapshot | null;
+  additional_windows: UsageWindow[];
+  credits_extra: Record<string, unknown>;
+  raw_payload: unknown | null;
+  fetched_at: string | null;
+  freshness: UsageFreshness;
+}
+
+export interface UsageWindow {
+  name: string;
+  used_percent: number;
+  window_minutes: number | null;
+  resets_at: number | null;
 }
 
+export interface SnapshotWindow {
+  used_percent: number;
+  window_minutes: number | null;
+  resets_at: number | null;
+}
+
+export interface HeaderLimit {
+  limit: number | null;
+  remaining: number | null;
+  resets_at: number | null;
+}
+
+export interface RateLimitSnapshot {
+  captured_at: string;
+  primary: SnapshotWindow | null;
+  secondary: SnapshotWindow | null;
+  has_credits: boolean | null;
+  unlimited_credits: boolean | null;
+  credits_balance: string | null;
+  requests: HeaderLimit | null;
+  tokens: HeaderLimit | null;
+}
+
+export interface ApiKeyUsage {
+  period_start: string | null;
+  input_tokens: number | null;
+  cached_input_tokens: number | null;
+  output_tokens: number | null;
+  requests: number | null;
+  cost: number | null;
+  currency: string | null;
+  organization_access: boolean;
+}
+
+export type UsageErrorKind =
+  | "login_required"
+  | "unauthorized"
+  | "forbidden"
+  | "rate_limited"
+  | "server_error"
+  | "network"
+  | "parse"
+  | "unsupported"
+  | "other";
+
+export interface UsageError {
+  kind: UsageErrorKind;
+  message: string;
+  status: number | null;
+  retry_after_secs: number | null;
+  retryable: boolean;
+}
+
+export type UsageFreshness = "fresh" | "cached" | "stale_after_error";
+
 export interface OAuthLoginInfo {
+  session_id: string;
   auth_url: string;
   callback_port: number;
 }
 
+export interface DeviceLoginInfo {
+  session_id: string;
+  verification_uri: string;
+  verification_uri_complete: string | null;
+  user_code: string;
+  expires_in: number;
+  interval: number;
+}
+
+export type LoginFlow = "callback" | "device" | "manual";
+
+export type OAuthProgressStage =
+  | "listening"
+  | "browser_opened"
+  | "waiting_for_approval"
+  | "callback_received"
+  | "exchanging"
+  | "completed"
+  | "failed"
+  | "cancelled"
+  | "timed_out";
+
+/** Payload of the `oauth-login-progress` event */
+export interface OAuthProgressEvent {
+  session_id: string;
+  stage: OAuthProgressStage;
+  message: string | null;
+}
+
+export interface PendingLoginInfo {
+  session_id: string;
+  account_name: string;
+  flow: LoginFlow;
+  callback_port: number | null;
+  started_at: string;
+}
+
 export interface AccountWithUsage extends AccountInfo {
   usage?: UsageInfo;
   usageLoading?: boolean;
 }
 
+export interface WindowSummary {
+  accounts: number;
+  remaining_percent_total: number;
+  average_used_percent: number | null;
+  exhausted_accounts: number;
+  next_reset_at: number | null;
+}
+
+export interface PlanUsageSummary {
+  plan_type: string;
+  accounts: number;
+  exhausted_accounts: number;
+  primary_remaining_percent: number;
+  secondary_remaining_percent: number;
+}
+
+export interface UsageSummary {
+  total_accounts: number;
+  accounts_with_data: number;
+  exhausted_accounts: number;
+  usable_next_hour_accounts: number;
+  primary: WindowSummary;
+  secondary: WindowSummary;
+  next_reset_at: number | null;
+  credits_totals: CreditsTotal[];
+  unlimited_credit_accounts: number;
+  plans: PlanUsageSummary[];
+  generated_at: string;
+}
+
+export interface CreditsTotal {
+  currency: string | null;
+  amount: number;
+  accounts: number;
+}
+
 export interface CodexProcessInfo {
   count: number;
   background_count: number;
@@ -62,12 +221,168 @@ export interface NotificationSettings {
   primary_threshold: number | null;
   secondary_threshold: number | null;
   credits_threshold: number | null;
+  credits_balance_threshold: number | null;
+  credits_currency: string | null;
+  credits_budget: number | null;
   min_interval_minutes: number;
+  notify_on_reset: boolean;
+  escalation_levels: number[];
+  hysteresis_percent: number;
+  sinks: NotificationSinkConfig[] | null;
+  quiet_hours: QuietHours | null;
+  templates: Record<string, NotificationTemplate>;
+  health_failure_threshold: number | null;
+  health_cooldown_minutes: number;
+}
+
+export interface NotificationTemplate {
+  title: string | null;
+  body: string | null;
+}
+
+/** Per-account overrides: a missing field inherits the global default */
+export type NotificationOverrides = Partial<NotificationSettings>;
+
+export interface QuietHours {
+  enabled: boolean;
+  start: string;
+  end: string;
+  weekdays: number[];
+  time_zone: string | null;
+  allow_critical: boolean;
 }
 
 export interface LastNotifications {
   primary: string | null;
   secondary: string | null;
   credits: string | null;
+  primary_levels: WindowLevels;
+  secondary_levels: WindowLevels;
+}
+
+export interface WindowLevels {
+  resets_at: number | null;
+  fired: number[];
+}
+
+
+export interface EndpointSettings {
+  issuer: string | null;
+  client_id: string | null;
+  chatgpt_backend_api: string | null;
+  openai_api: string | null;
+}
+
+export interface LoginSettings {
+  callback_timeout_secs: number;
+}
+
+export interface HttpSettings {
+  request_timeout_secs: number;
+  connect_timeout_secs: number;
+  pool_idle_timeout_secs: number;
+  proxy: string | null;
+}
+
+export interface UsageSettings {
+  refresh_concurrency: number;
+  cache_ttl_secs: number;
+  persist_cache: boolean;
+}
+
+export interface AppSettings {
+  endpoints: EndpointSettings;
+  login: LoginSettings;
+  http: HttpSettings;
+  usage: UsageSettings;
+  notifications: GlobalNotificationSettings;
+  processes: ProcessSettings;
+}
+
+export type AppServerRestartPolicy = "never" | "ask" | "always";
+
+export interface ProcessSettings {
+  app_server_restart: AppServerRestartPolicy;
+  terminate_timeout_secs: number;
+}
+
+export interface AppServerRestartResult {
+  restarted_pids: number[];
+  forced_pids: number[];
+  failed_pids: number[];
+}
+
+export interface SwitchAccountResult {
+  pending_restart_pids: number[];
+  restart: AppServerRestartResult | null;
+}
+
+export interface GlobalNotificationSettings {
+  defaults: NotificationSettings;
+  sinks: NotificationSinkConfig[];
+  delivery_retries: number;
+  quiet_hours: QuietHours | null;
+  history_retention_days: number;
+  history_max_entries: number;
+}
+
+export type NotificationSinkConfig =
+  | { type: "desktop" }
+  | {
+      type: "webhook";
+      url: string;
+      body_template: string | null;
+      headers: Record<string, string>;
+    }
+  | { type: "slack"; webhook_url: string }
+  | { type: "discord"; webhook_url: string };
+
+export type NotificationOutcome =
+  | "delivered"
+  | "partially_delivered"
+  | "failed"
+  | "queued";
+
+export interface NotificationHistoryEntry {
+  timestamp: string;
+  account_id: string | null;
+  account_name: string;
+  event: string;
+  title: string;
+  body: string;
+  value: number | null;
+  critical: boolean;
+  outcome: NotificationOutcome;
+  deliveries: SinkDeliveryResult[];
+}
+
+export type HealthCheck = "token_refresh" | "usage_fetch" | "warmup";
+
+export interface AccountHealthIssue {
+  account_id: string;
+  check: HealthCheck;
+  consecutive_failures: number;
+  last_error: string;
+  first_failed_at: string;
+  alerted_at: string | null;
+}
+
+export interface SinkDeliveryResult {
+  sink: string;
+  success: boolean;
+  attempts: number;
+  error: string | null;
+}
+
+export interface EndpointConfig {
+  issuer: string;
+  client_id: string;
+  chatgpt_backend_api: string;
+  openai_api: string;
 }
 
+export interface RevocationResult {
+  requested: boolean;
+  revoked: boolean;
+  error: string | null;
+}